@@ -267,6 +267,15 @@ pub struct TruncationParamsDef {
     #[serde(with = "TruncationStrategyDef")]
     strategy: tk::TruncationStrategy,
     stride: usize,
+    #[serde(with = "TruncationDirectionDef")]
+    direction: tk::TruncationDirection,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "tk::TruncationDirection", rename_all = "camelCase")]
+pub enum TruncationDirectionDef {
+    Left,
+    Right,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -338,6 +347,7 @@ pub struct PaddingParamsDef {
     pad_id: u32,
     pad_type_id: u32,
     pad_token: String,
+    strict: bool,
 }
 #[derive(Serialize, Deserialize)]
 #[serde(transparent)]
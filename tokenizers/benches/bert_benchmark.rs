@@ -0,0 +1,56 @@
+#[macro_use]
+extern crate criterion;
+
+use criterion::{black_box, Criterion};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokenizers::models::wordpiece::WordPiece;
+use tokenizers::pre_tokenizers::whitespace::Whitespace;
+use tokenizers::tokenizer::{Model, NormalizedString, Offsets, PreTokenizer};
+
+fn iter_bench_tokenize(
+    iters: u64,
+    model: &WordPiece,
+    sentences: &[Vec<(String, Offsets)>],
+) -> Duration {
+    let mut duration = Duration::new(0, 0);
+    let mut sentence_index: usize = 0;
+    for _i in 0..iters {
+        if sentence_index >= sentences.len() {
+            sentence_index = 0;
+        }
+        let sentence = sentences[sentence_index].clone();
+        let start = Instant::now();
+        let _ = black_box(model.tokenize(sentence));
+        duration = duration.checked_add(start.elapsed()).unwrap();
+        sentence_index += 1;
+    }
+    duration
+}
+
+fn bench_wordpiece_tokenize(c: &mut Criterion) {
+    let wordpiece = WordPiece::from_files("data/bert-base-uncased-vocab.txt")
+        .build()
+        .unwrap();
+    let mut sentences: Vec<Vec<(String, Offsets)>> = vec![];
+    for line in BufReader::new(File::open(Path::new("data/big.txt")).unwrap()).lines() {
+        let mut normalized = NormalizedString::from(&line.unwrap().to_lowercase());
+        let words = Whitespace.pre_tokenize(&mut normalized).unwrap();
+        if !words.is_empty() {
+            sentences.push(words);
+        }
+    }
+
+    c.bench_function("WordPiece BERT tokenize", |b| {
+        b.iter_custom(|iters| iter_bench_tokenize(iters, &wordpiece, &sentences))
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(20);
+    targets = bench_wordpiece_tokenize
+}
+criterion_main!(benches);
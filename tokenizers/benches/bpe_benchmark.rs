@@ -9,7 +9,7 @@ use std::time::{Duration, Instant};
 use tokenizers::models::bpe::{BpeTrainerBuilder, BPE};
 use tokenizers::pre_tokenizers::byte_level::ByteLevel;
 use tokenizers::pre_tokenizers::whitespace::Whitespace;
-use tokenizers::tokenizer::{AddedToken, EncodeInput, Tokenizer, Trainer};
+use tokenizers::tokenizer::{AddedToken, EncodeInput, Model, Tokenizer, Trainer};
 
 static BATCH_SIZE: usize = 1_000;
 
@@ -95,6 +95,103 @@ fn bench_gpt2(c: &mut Criterion) {
     });
 }
 
+fn bench_batch_dedup(c: &mut Criterion) {
+    let bpe = BPE::from_files("data/gpt2-vocab.json", "data/gpt2-merges.txt")
+        .build()
+        .unwrap();
+    let tokenizer = create_gpt2_tokenizer(bpe);
+    // 90% of the inputs are duplicates
+    let lines = BufReader::new(File::open(Path::new("data/big.txt")).unwrap())
+        .lines()
+        .take(BATCH_SIZE / 10)
+        .map(|line| line.unwrap())
+        .collect::<Vec<_>>();
+    let batch = lines
+        .iter()
+        .cycle()
+        .take(BATCH_SIZE)
+        .map(|line| EncodeInput::from(line.as_str()))
+        .collect::<Vec<_>>();
+
+    c.bench_function("BPE GPT2 encode batch, 90% duplicates", |b| {
+        b.iter(|| black_box(tokenizer.encode_batch(batch.clone(), false)))
+    });
+
+    c.bench_function("BPE GPT2 encode batch dedup, 90% duplicates", |b| {
+        b.iter(|| black_box(tokenizer.encode_batch_dedup(batch.clone(), false)))
+    });
+}
+
+fn bench_count_tokens(c: &mut Criterion) {
+    let bpe = BPE::from_files("data/gpt2-vocab.json", "data/gpt2-merges.txt")
+        .build()
+        .unwrap();
+    let tokenizer = create_gpt2_tokenizer(bpe);
+    let text = std::fs::read_to_string("data/small.txt").unwrap();
+
+    c.bench_function("BPE GPT2 encode len, small.txt", |b| {
+        b.iter(|| black_box(tokenizer.encode(text.as_str(), false).unwrap().len()))
+    });
+
+    c.bench_function("BPE GPT2 count tokens, small.txt", |b| {
+        b.iter(|| black_box(tokenizer.count_tokens(text.as_str(), false).unwrap()))
+    });
+}
+
+fn bench_encode_ids(c: &mut Criterion) {
+    let bpe = BPE::from_files("data/gpt2-vocab.json", "data/gpt2-merges.txt")
+        .build()
+        .unwrap();
+    let tokenizer = create_gpt2_tokenizer(bpe);
+    let text = std::fs::read_to_string("data/small.txt").unwrap();
+
+    c.bench_function("BPE GPT2 encode ids, small.txt", |b| {
+        b.iter(|| {
+            black_box(
+                tokenizer
+                    .encode(text.as_str(), false)
+                    .unwrap()
+                    .get_ids()
+                    .to_vec(),
+            )
+        })
+    });
+
+    c.bench_function("BPE GPT2 encode_ids, small.txt", |b| {
+        b.iter(|| black_box(tokenizer.encode_ids(text.as_str(), false).unwrap()))
+    });
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let bpe = BPE::from_files("data/gpt2-vocab.json", "data/gpt2-merges.txt")
+        .build()
+        .unwrap();
+    let tokenizer = create_gpt2_tokenizer(bpe.clone());
+    let ids = (0..4096).collect::<Vec<u32>>();
+
+    c.bench_function("BPE GPT2 decode 4096 tokens", |b| {
+        b.iter(|| black_box(tokenizer.decode(ids.clone(), false)))
+    });
+
+    let tokenizer = Tokenizer::new(Box::new(bpe));
+    c.bench_function("BPE GPT2 decode 4096 tokens, no decoder", |b| {
+        b.iter(|| black_box(tokenizer.decode(ids.clone(), false)))
+    });
+}
+
+fn bench_long_word(c: &mut Criterion) {
+    let bpe = BPE::from_files("data/gpt2-vocab.json", "data/gpt2-merges.txt")
+        .cache_capacity(0)
+        .build()
+        .unwrap();
+    let word = "abcdefghij".repeat(1_000);
+    let sentence = vec![(word.clone(), (0, word.len()))];
+
+    c.bench_function("BPE GPT2 tokenize 10k chars word", |b| {
+        b.iter(|| black_box(bpe.tokenize(sentence.clone())))
+    });
+}
+
 #[allow(clippy::borrowed_box)]
 fn iter_bench_train(
     iters: u64,
@@ -143,7 +240,8 @@ fn bench_train(c: &mut Criterion) {
 criterion_group! {
     name = benches;
     config = Criterion::default().sample_size(20);
-    targets = bench_gpt2
+    targets = bench_gpt2, bench_decode, bench_long_word, bench_batch_dedup, bench_count_tokens,
+        bench_encode_ids
 }
 criterion_group! {
     name = benches_train;
@@ -0,0 +1,95 @@
+use crate::tokenizer::{Decoder, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Everything from the `ByteLevel` pre-tokenizer, which can still be used as a decoder, stays
+// available from here
+pub use crate::pre_tokenizers::byte_level::*;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The bytes represented by the tokens are not valid UTF-8
+    InvalidUtf8(std::string::FromUtf8Error),
+}
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidUtf8(e) => write!(fmt, "ByteLevel error: Invalid UTF-8 output: {}", e),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+/// Reverses the byte-to-char table of the `ByteLevel` pre-tokenizer: the tokens are joined, and
+/// each of their chars is converted back to the byte it represents, so that a char split across
+/// several tokens gets rebuilt. Any char outside of this table, like the ones of an added token,
+/// keeps its own UTF-8 bytes.
+///
+/// Unlike the `ByteLevel` pre-tokenizer used as a decoder, which replaces the invalid sequences
+/// with `U+FFFD`, this returns an error when the bytes are not valid UTF-8.
+pub struct ByteLevelDecoder;
+
+#[typetag::serde]
+impl Decoder for ByteLevelDecoder {
+    fn decode(&self, tokens: Vec<String>) -> Result<String> {
+        let mut bytes = vec![];
+        for c in tokens.concat().chars() {
+            match CHAR_BYTES.get(&c) {
+                Some(b) => bytes.push(*b),
+                None => bytes.extend(c.to_string().as_bytes()),
+            }
+        }
+        String::from_utf8(bytes).map_err(|e| Error::InvalidUtf8(e).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::{NormalizedString, PreTokenizer};
+
+    #[test]
+    fn round_trip() {
+        let pre_tokenizer = ByteLevel::default().add_prefix_space(false);
+        for input in &[
+            "Hello there, how are you?",
+            "café 🤗 emoji",
+            "東京に行きます",
+        ] {
+            let mut normalized = NormalizedString::from(*input);
+            let words = pre_tokenizer
+                .pre_tokenize(&mut normalized)
+                .unwrap()
+                .into_iter()
+                .map(|(word, _)| word)
+                .collect::<Vec<_>>();
+            assert_eq!(&ByteLevelDecoder.decode(words.clone()).unwrap(), input);
+
+            // Each char of a multi-byte codepoint in its own token
+            let chars = words
+                .iter()
+                .flat_map(|w| w.chars())
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>();
+            assert_eq!(&ByteLevelDecoder.decode(chars).unwrap(), input);
+        }
+    }
+
+    #[test]
+    fn added_tokens_and_invalid_utf8() {
+        let tokens = vec!["Hello".into(), "Ġthere".into(), "Ġ".into(), "[PA D]".into()];
+        assert_eq!(
+            ByteLevelDecoder.decode(tokens).unwrap(),
+            "Hello there [PA D]"
+        );
+
+        // The first two bytes of `🤗` only
+        let err = ByteLevelDecoder.decode(vec!["ðŁ".into()]).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::InvalidUtf8(_))
+        ));
+    }
+}
@@ -0,0 +1,93 @@
+use crate::tokenizer::{Decoder, Result};
+use serde::{Deserialize, Serialize};
+
+// Everything from the `Metaspace` pre-tokenizer, which can still be used as a decoder, stays
+// available from here
+pub use crate::pre_tokenizers::metaspace::*;
+
+#[derive(Serialize, Deserialize)]
+/// Decodes tokens where the `replacement` char marks the start of words, as produced
+/// by SentencePiece models. It can be configured independently of any pre-tokenizer.
+pub struct MetaspaceDecoder {
+    /// The char marking the start of words
+    replacement: char,
+    /// Whether the very first `replacement` of the output was added as a prefix space,
+    /// and should thus be removed
+    add_prefix_space: bool,
+}
+
+impl MetaspaceDecoder {
+    pub fn new(replacement: char, add_prefix_space: bool) -> Self {
+        Self {
+            replacement,
+            add_prefix_space,
+        }
+    }
+}
+
+impl Default for MetaspaceDecoder {
+    fn default() -> Self {
+        Self::new('▁', true)
+    }
+}
+
+#[typetag::serde]
+impl Decoder for MetaspaceDecoder {
+    fn decode(&self, tokens: Vec<String>) -> Result<String> {
+        let mut output = tokens.concat();
+        // Only the very first char of the output can be the prefix space, whatever the token
+        // it comes from (some empty tokens, or removed special tokens may precede it)
+        if self.add_prefix_space && output.starts_with(self.replacement) {
+            output.drain(..self.replacement.len_utf8());
+        }
+        Ok(output.replace(self.replacement, " "))
+    }
+
+    /// Each group is decoded as if it was a sequence on its own, and the groups are then
+    /// separated by a single space.
+    fn decode_groups(&self, groups: Vec<Vec<String>>) -> Result<String> {
+        Ok(groups
+            .into_iter()
+            .map(|group| self.decode(group))
+            .collect::<Result<Vec<_>>>()?
+            .join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_without_pre_tokenizer() {
+        let decoder = MetaspaceDecoder::default();
+        let res = decoder
+            .decode(vec!["▁Hey".into(), "▁friend".into(), "!".into()])
+            .unwrap();
+        assert_eq!(&res, "Hey friend!");
+
+        let decoder = MetaspaceDecoder::new('▁', false);
+        let res = decoder
+            .decode(vec!["▁Hey".into(), "▁friend".into(), "!".into()])
+            .unwrap();
+        assert_eq!(&res, " Hey friend!");
+
+        let decoder = MetaspaceDecoder::new('_', true);
+        let res = decoder
+            .decode(vec!["_Hey".into(), "_fri".into(), "end▁".into()])
+            .unwrap();
+        assert_eq!(&res, "Hey friend▁");
+    }
+
+    #[test]
+    fn serialization() {
+        let decoder: Box<dyn Decoder> = Box::new(MetaspaceDecoder::default());
+        let serialized = serde_json::to_string(&decoder).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"MetaspaceDecoder","replacement":"▁","add_prefix_space":true}"#
+        );
+        let decoder: Box<dyn Decoder> = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(&decoder.decode(vec!["▁a".into()]).unwrap(), "a");
+    }
+}
@@ -1,6 +1,21 @@
 pub mod bpe;
+pub mod byte_level;
+pub mod metaspace;
 pub mod wordpiece;
 
-// Re-export these as decoders
-pub use super::pre_tokenizers::byte_level;
-pub use super::pre_tokenizers::metaspace;
+/// Remove the spaces left before punctuation and some contractions by decoders joining
+/// their tokens with spaces, like in `Hello , I 'm here .`
+pub(crate) fn cleanup(dirty_input: &str) -> String {
+    dirty_input
+        .replace(" .", ".")
+        .replace(" ?", "?")
+        .replace(" !", "!")
+        .replace(" ,", ",")
+        .replace(" ' ", "'")
+        .replace(" n't", "n't")
+        .replace(" 'm", "'m")
+        .replace(" do not", " don't")
+        .replace(" 's", "'s")
+        .replace(" 've", "'ve")
+        .replace(" 're", "'re")
+}
@@ -31,20 +31,33 @@ impl Decoder for WordPiece {
     fn decode(&self, tokens: Vec<String>) -> Result<String> {
         let mut output = tokens.join(" ").replace(&format!(" {}", self.prefix), "");
         if self.cleanup {
-            output = output
-                .replace(" .", ".")
-                .replace(" ?", "?")
-                .replace(" !", "!")
-                .replace(" ,", ",")
-                .replace(" ' ", "'")
-                .replace(" n't", "n't")
-                .replace(" 'm", "'m")
-                .replace(" do not", " don't")
-                .replace(" 's", "'s")
-                .replace(" 've", "'ve")
-                .replace(" 're", "'re");
+            output = super::cleanup(&output);
         }
 
         Ok(output)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode() {
+        let decoder = WordPiece::default();
+        assert_eq!(
+            decoder
+                .decode(vec!["welcome".into(), "##s".into(), "home".into()])
+                .unwrap(),
+            "welcomes home"
+        );
+        // A leading continuation has nothing to attach to, so it is kept as is
+        assert_eq!(
+            decoder
+                .decode(vec!["##s".into(), "home".into(), "##s".into()])
+                .unwrap(),
+            "##s homes"
+        );
+        assert_eq!(decoder.decode(vec![]).unwrap(), "");
+    }
+}
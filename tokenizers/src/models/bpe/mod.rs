@@ -23,8 +23,13 @@ pub enum Error {
     BadMerges(usize),
     /// If a token found in merges, is not in the vocab
     MergeTokenOutOfVocabulary(String),
+    /// If a merge references ids that are not in the vocab. This error holds the rank of
+    /// the merge, its pair and the id of the merged token.
+    MergeIdOutOfVocabulary(u32, Pair, u32),
     /// If the provided unk token is out of vocabulary
     UnkTokenOutOfVocabulary(String),
+    /// If a token is out of vocabulary while no unk token was provided
+    MissingUnkToken(String),
     /// Dropout not between 0 and 1.
     InvalidDropout,
 }
@@ -51,9 +56,20 @@ impl std::fmt::Display for Error {
             Error::MergeTokenOutOfVocabulary(token) => {
                 write!(f, "Token `{}` out of vocabulary", token)
             }
+            Error::MergeIdOutOfVocabulary(rank, (a, b), new_id) => write!(
+                f,
+                "Merge of rank {} ({} {} -> {}) references ids out of vocabulary, the vocab \
+                 and merges probably come from different trainings",
+                rank, a, b, new_id
+            ),
             Error::UnkTokenOutOfVocabulary(token) => {
                 write!(f, "Unk token `{}` not found in the vocabulary", token)
             }
+            Error::MissingUnkToken(token) => write!(
+                f,
+                "Token `{}` out of vocabulary, and no unk token to replace it",
+                token
+            ),
             Error::InvalidDropout => write!(f, "Dropout should be between 0 and 1"),
         }
     }
@@ -11,12 +11,35 @@ use std::{
     io::prelude::*,
     io::{BufRead, BufReader},
     path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
 };
 
 type Vocab = HashMap<String, u32>;
 type VocabR = HashMap<u32, String>;
 type Merges = HashMap<Pair, (u32, u32)>;
 
+/// Whether we already warned about a model without unk token
+static MISSING_UNK_WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Warn that the models without unk token fail on any token out of their vocab. This happens
+/// only once, and returns whether this call is the one that warned.
+fn warn_missing_unk() -> bool {
+    let warn = !MISSING_UNK_WARNED.swap(true, Ordering::Relaxed);
+    if warn {
+        println!(
+            "Warning: BPE model built without unk token, encoding any token out of its \
+             vocabulary will fail"
+        );
+    }
+    warn
+}
+
+/// Whether building a model with this config should warn about its missing unk token. An
+/// empty vocab doesn't need one, since it probably is waiting to be trained.
+fn should_warn_missing_unk(config: &Config) -> bool {
+    config.warn_missing_unk && config.unk_token.is_none() && !config.vocab.is_empty()
+}
+
 struct Config {
     files: Option<(String, String)>,
     vocab: Vocab,
@@ -26,6 +49,7 @@ struct Config {
     unk_token: Option<String>,
     continuing_subword_prefix: Option<String>,
     end_of_word_suffix: Option<String>,
+    warn_missing_unk: bool,
 }
 
 /// A `BpeBuilder` can be used to create a `BPE` model with a custom configuration.
@@ -45,6 +69,7 @@ impl Default for BpeBuilder {
                 unk_token: None,
                 continuing_subword_prefix: None,
                 end_of_word_suffix: None,
+                warn_missing_unk: true,
             },
         }
     }
@@ -99,6 +124,14 @@ impl BpeBuilder {
         self
     }
 
+    /// Set whether to warn when building a model without `UNK` token, which then fails to
+    /// encode any token out of its vocab. This warning is printed once at most, whether the
+    /// model gets built directly, loaded or trained.
+    pub fn warn_missing_unk(mut self, warn: bool) -> Self {
+        self.config.warn_missing_unk = warn;
+        self
+    }
+
     /// Returns a `BPE` model that uses the `BpeBuilder`'s configuration.
     pub fn build(mut self) -> Result<BPE> {
         // Validate dropout.
@@ -115,17 +148,45 @@ impl BpeBuilder {
             self.config.merges = m;
         }
 
-        let vocab_r = self
+        let vocab_r: VocabR = self
             .config
             .vocab
             .iter()
             .map(|(key, val)| (*val, key.to_owned()))
             .collect();
+
+        // Every merge must reference tokens of this vocab, we report the first one that doesn't
+        if let Some((pair, (rank, new_id))) = self
+            .config
+            .merges
+            .iter()
+            .filter(|((a, b), (_, new_id))| {
+                [a, b, new_id].iter().any(|id| !vocab_r.contains_key(*id))
+            })
+            .min_by_key(|(_, (rank, _))| *rank)
+        {
+            return Err(Error::MergeIdOutOfVocabulary(*rank, *pair, *new_id).into());
+        }
         let cache = match self.config.cache_capacity {
             0 => None,
             capacity => Some(Cache::new(capacity)),
         };
 
+        // Resolve the unk id once for all. An empty vocab is allowed to not contain it, since
+        // it probably is waiting to be trained.
+        let unk_id = self
+            .config
+            .unk_token
+            .as_ref()
+            .and_then(|unk| self.config.vocab.get(unk).copied());
+        if let Some(unk) = &self.config.unk_token {
+            if unk_id.is_none() && !self.config.vocab.is_empty() {
+                return Err(Error::UnkTokenOutOfVocabulary(unk.to_owned()).into());
+            }
+        } else if should_warn_missing_unk(&self.config) {
+            warn_missing_unk();
+        }
+
         Ok(BPE {
             vocab: self.config.vocab,
             vocab_r,
@@ -133,6 +194,7 @@ impl BpeBuilder {
             cache,
             dropout: self.config.dropout,
             unk_token: self.config.unk_token,
+            unk_id,
             continuing_subword_prefix: self.config.continuing_subword_prefix,
             end_of_word_suffix: self.config.end_of_word_suffix,
         })
@@ -145,6 +207,10 @@ pub struct BPE {
     /// The vocabulary assigns a number to each token.
     pub(crate) vocab: Vocab,
     /// Reversed vocabulary, to rebuild sentences.
+    ///
+    /// This duplicates the token strings of `vocab`. We can't have `vocab` reference interned
+    /// strings instead, since `Model::get_vocab` exposes it as a `HashMap<String, u32>`.
+    /// `merges` on the other hand only stores ids.
     pub(crate) vocab_r: VocabR,
     /// Contains the mapping between Pairs and their (rank, new_id).
     pub(crate) merges: Merges,
@@ -155,6 +221,8 @@ pub struct BPE {
     pub(super) dropout: Option<f32>,
     /// The unknown token to be used when we encounter an unknown char
     pub(super) unk_token: Option<String>,
+    /// The id of the unknown token, resolved when building the model
+    unk_id: Option<u32>,
     /// An optional prefix to use on any subword that exist only behind another one
     pub(super) continuing_subword_prefix: Option<String>,
     /// An optional suffix to caracterize and end-of-word subword
@@ -192,6 +260,7 @@ impl Clone for BPE {
             cache: fresh_cache,
             dropout: self.dropout,
             unk_token: self.unk_token.clone(),
+            unk_id: self.unk_id,
             continuing_subword_prefix: self.continuing_subword_prefix.clone(),
             end_of_word_suffix: self.end_of_word_suffix.clone(),
         }
@@ -323,11 +392,12 @@ impl BPE {
                 word.add(*id);
             } else if let Some(unk) = &self.unk_token {
                 let unk_id = self
-                    .vocab
-                    .get(unk)
+                    .unk_id
                     .ok_or_else(|| Error::UnkTokenOutOfVocabulary(unk.to_owned()))?;
                 // Handle UNK token
-                word.add(*unk_id);
+                word.add_unk(unk_id);
+            } else {
+                return Err(Error::MissingUnkToken(s).into());
             }
         }
 
@@ -345,14 +415,16 @@ impl BPE {
         word.get_chars()
             .iter()
             .zip(word.get_offsets())
-            .map(|(id, offsets)| {
+            .zip(word.get_unks())
+            .map(|((id, offsets), unk)| {
                 assert!(initial_offsets.0 + offsets.0 < initial_offsets.1);
-                Token::new(
-                    *id,
-                    self.vocab_r[id].clone(),
-                    (initial_offsets.0 + offsets.0, initial_offsets.0 + offsets.1),
-                    index,
-                )
+                let value = self.vocab_r[id].clone();
+                let offsets = (initial_offsets.0 + offsets.0, initial_offsets.0 + offsets.1);
+                if unk {
+                    Token::new_unk(*id, value, offsets, index)
+                } else {
+                    Token::new(*id, value, offsets, index)
+                }
             })
             .collect::<Vec<_>>()
     }
@@ -433,6 +505,10 @@ impl Model for BPE {
         self.vocab.get(token).copied()
     }
 
+    fn contains_token(&self, token: &str) -> bool {
+        self.vocab.contains_key(token)
+    }
+
     fn id_to_token(&self, id: u32) -> Option<&str> {
         self.vocab_r.get(&id).map(String::as_ref)
     }
@@ -578,6 +654,104 @@ mod tests {
         assert!(!tokens.is_empty() && tokens.len() <= 9);
     }
 
+    #[test]
+    fn test_contains_token() {
+        let vocab: Vocab = [("a".into(), 0), ("b".into(), 1)].iter().cloned().collect();
+        let bpe = BPE::new(vocab, HashMap::new());
+
+        assert!(bpe.contains_token("a"));
+        assert!(bpe.contains_token("b"));
+        assert!(!bpe.contains_token("ab"));
+    }
+
+    #[test]
+    fn test_vocab_round_trip() {
+        let vocab: Vocab = [("a".into(), 0), ("b".into(), 1), ("ab".into(), 2)]
+            .iter()
+            .cloned()
+            .collect();
+        let merges: Merges = [((0, 1), (0, 2))].iter().cloned().collect();
+        let bpe = BPE::new(vocab.clone(), merges);
+
+        assert_eq!(bpe.get_vocab_size(), vocab.len());
+        for (token, id) in &vocab {
+            assert_eq!(bpe.token_to_id(token), Some(*id));
+            assert_eq!(bpe.id_to_token(*id), Some(token.as_str()));
+        }
+        assert_eq!(bpe.id_to_token(3), None);
+    }
+
+    #[test]
+    fn test_unk_token() {
+        let vocab: Vocab = [("<unk>".into(), 0), ("a".into(), 1), ("b".into(), 2)]
+            .iter()
+            .cloned()
+            .collect();
+        let bpe = BPE::builder()
+            .vocab_and_merges(vocab, HashMap::new())
+            .unk_token("<unk>".into())
+            .build()
+            .unwrap();
+        let tokens = bpe.tokenize(vec![("abc".into(), (0, 3))]).unwrap();
+        assert_eq!(
+            tokens.iter().map(|t| t.id).collect::<Vec<_>>(),
+            vec![1, 2, 0]
+        );
+        assert_eq!(
+            tokens.iter().map(|t| t.unk).collect::<Vec<_>>(),
+            vec![false, false, true]
+        );
+
+        // A missing unk token is reported when building the model
+        let vocab: Vocab = [("a".into(), 0)].iter().cloned().collect();
+        match BPE::builder()
+            .vocab_and_merges(vocab, HashMap::new())
+            .unk_token("<unk>".into())
+            .build()
+        {
+            Ok(_) => unreachable!(),
+            Err(err) => match err.downcast_ref::<Error>() {
+                Some(Error::UnkTokenOutOfVocabulary(token)) => assert_eq!(token, "<unk>"),
+                _ => unreachable!(),
+            },
+        }
+
+        // Unless the vocab is empty, waiting for the model to be trained
+        let bpe = BPE::builder().unk_token("<unk>".into()).build().unwrap();
+        assert!(bpe.tokenize(vec![("a".into(), (0, 1))]).is_err());
+    }
+
+    #[test]
+    fn test_missing_unk_token() {
+        let vocab: Vocab = [("a".into(), 0)].iter().cloned().collect();
+        let builder = BPE::builder().vocab_and_merges(vocab, HashMap::new());
+        assert!(should_warn_missing_unk(&builder.config));
+        let bpe = builder.build().unwrap();
+
+        // The warning happens only once, whichever model got built first
+        assert!(MISSING_UNK_WARNED.load(Ordering::Relaxed));
+        assert!(!warn_missing_unk());
+        assert!(!warn_missing_unk());
+
+        // Loading a model warns too, unless disabled, or while waiting to be trained
+        let builder = BPE::from_files("vocab.json", "merges.txt");
+        assert!(builder.config.warn_missing_unk);
+        let builder = BPE::builder().vocab_and_merges(bpe.vocab.clone(), HashMap::new());
+        assert!(!should_warn_missing_unk(
+            &builder.warn_missing_unk(false).config
+        ));
+        assert!(!should_warn_missing_unk(&BPE::builder().config));
+
+        assert!(bpe.tokenize(vec![("aa".into(), (0, 2))]).is_ok());
+        match bpe.tokenize(vec![("ab".into(), (0, 2))]) {
+            Ok(_) => unreachable!(),
+            Err(err) => match err.downcast_ref::<Error>() {
+                Some(Error::MissingUnkToken(token)) => assert_eq!(token, "b"),
+                _ => unreachable!(),
+            },
+        }
+    }
+
     #[test]
     // Ensure `BPE::from_files` works as expected.
     fn test_bpe_from_files() {
@@ -638,6 +812,29 @@ mod tests {
         }
     }
 
+    #[test]
+    // Ensure `MergeIdOutOfVocabulary` error is returned for mismatched vocab and merges.
+    fn test_bpe_merge_id_oov() {
+        let vocab: Vocab = [("a".into(), 0), ("b".into(), 1), ("ab".into(), 2)]
+            .iter()
+            .cloned()
+            .collect();
+        let merges: Merges = [((0, 1), (0, 2)), ((2, 1), (1, 3)), ((0, 7), (2, 2))]
+            .iter()
+            .cloned()
+            .collect();
+
+        match BPE::builder().vocab_and_merges(vocab, merges).build() {
+            Ok(_) => unreachable!(),
+            Err(err) => match err.downcast_ref::<Error>() {
+                Some(Error::MergeIdOutOfVocabulary(rank, pair, new_id)) => {
+                    assert_eq!((*rank, *pair, *new_id), (1, (2, 1), 3))
+                }
+                _ => unreachable!(),
+            },
+        }
+    }
+
     #[test]
     // Ensure `BadMerges` error is returned when there is an invalid line in the
     // merges.txt file.
@@ -40,6 +40,8 @@ struct Symbol {
     prev: isize,
     next: isize,
     len: usize,
+    /// Whether this symbol stands for a char out of the vocabulary
+    unk: bool,
 }
 impl Symbol {
     /// Merges the current Symbol with the other one.
@@ -49,6 +51,7 @@ impl Symbol {
         self.c = new_c;
         self.len += other.len;
         self.next = other.next;
+        self.unk = false;
     }
 }
 
@@ -79,6 +82,15 @@ impl Word {
     }
 
     pub(super) fn add(&mut self, c: u32) {
+        self.add_symbol(c, false);
+    }
+
+    /// Add the id of the unknown token, for a char out of the vocabulary
+    pub(super) fn add_unk(&mut self, c: u32) {
+        self.add_symbol(c, true);
+    }
+
+    fn add_symbol(&mut self, c: u32, unk: bool) {
         let (prev, next) = {
             let len = self.symbols.len() as isize;
             if let Some(last) = self.symbols.last_mut() {
@@ -94,6 +106,7 @@ impl Word {
             prev,
             next,
             len: 1,
+            unk,
         });
     }
 
@@ -123,6 +136,7 @@ impl Word {
                     prev: first.prev,
                     next: second.next,
                     len: first.len + second.len,
+                    unk: false,
                 };
                 self.symbols.insert(i, new_s); // Insert replacement before first char of pair
                 self.symbols.remove(i + 1); // Remove first char of pair
@@ -227,25 +241,19 @@ impl Word {
             }
         }
 
-        // Filter out the removed symbols
-        let mut i = 0;
-        loop {
-            if self.symbols.len() <= i {
-                break;
-            }
-
-            if self.symbols[i].len == 0 {
-                self.symbols.remove(i);
-            } else {
-                i += 1;
-            }
-        }
+        // Filter out the removed symbols, in a single pass to stay linear on long words
+        self.symbols.retain(|s| s.len != 0);
     }
 
     pub(super) fn get_chars(&self) -> Vec<u32> {
         self.symbols.iter().map(|s| s.c).collect()
     }
 
+    /// Whether each symbol stands for a char out of the vocabulary
+    pub(super) fn get_unks(&self) -> Vec<bool> {
+        self.symbols.iter().map(|s| s.unk).collect()
+    }
+
     pub(super) fn get_offsets(&self) -> Vec<(usize, usize)> {
         let mut offsets = vec![];
         let mut pos = 0;
@@ -304,4 +312,22 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_merge_all_long_word() {
+        // 'a' ~= 0, 'aa' ~= 1, 'aaaa' ~= 2
+        let merges: HashMap<Pair, (u32, u32)> = [((0, 0), (0, 1)), ((1, 1), (1, 2))]
+            .iter()
+            .cloned()
+            .collect();
+        let mut word = Word::new();
+        (0..10_001).for_each(|_| word.add(0));
+
+        word.merge_all(&merges, None);
+
+        let mut expected = vec![2u32; 2_500];
+        expected.push(0);
+        assert_eq!(word.get_chars(), expected);
+        assert_eq!(word.get_offsets()[2_500], (10_000, 10_001));
+    }
 }
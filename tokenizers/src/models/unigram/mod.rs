@@ -0,0 +1,313 @@
+use crate::tokenizer::{Model, Result, Token};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+
+mod serialization;
+
+/// The score given to an unknown char, below the score of any piece, as in SentencePiece
+const UNK_PENALTY: f64 = 10.0;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The `unk_id` is not the id of a piece of the vocabulary
+    UnkIdNotInVocabulary(usize),
+    /// A word contains a char that is not in the vocabulary, while no `unk_id` was provided
+    MissingUnkId(String),
+}
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnkIdNotInVocabulary(unk_id) => write!(
+                fmt,
+                "Unigram error: The unk_id {} is not in the vocabulary",
+                unk_id
+            ),
+            Error::MissingUnkId(word) => write!(
+                fmt,
+                "Unigram error: Can't tokenize `{}` without an unk_id",
+                word
+            ),
+        }
+    }
+}
+
+/// A [Unigram language model](https://arxiv.org/abs/1804.10959), as trained by SentencePiece.
+/// Each piece of the vocabulary has a log probability, and each word gets segmented in the
+/// pieces that maximize the sum of their log probabilities.
+pub struct Unigram {
+    /// The pieces with their log probability, the index of each piece being its id
+    vocab: Vec<(String, f64)>,
+    /// The id of each piece
+    token_to_ids: HashMap<String, u32>,
+    /// The id of the piece used for any char out of the vocabulary. Without it, such a char
+    /// leads to an error.
+    unk_id: Option<usize>,
+    /// The lowest log probability of the vocabulary, to score the unknown chars below it
+    min_score: f64,
+    /// The length in chars of the longest piece, to bound the segmentation candidates
+    max_piece_len: usize,
+}
+
+impl fmt::Debug for Unigram {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Unigram")
+            .field("unk_id", &self.unk_id)
+            .field("vocab", &self.vocab.len())
+            .finish()
+    }
+}
+
+impl Default for Unigram {
+    fn default() -> Self {
+        Self::new(vec![], None).unwrap()
+    }
+}
+
+impl Unigram {
+    /// Create a `Unigram` model from its pieces and their log probability, and the optional
+    /// id of the unknown piece.
+    pub fn new(vocab: Vec<(String, f64)>, unk_id: Option<usize>) -> Result<Self> {
+        if let Some(unk_id) = unk_id {
+            if unk_id >= vocab.len() {
+                return Err(Box::new(Error::UnkIdNotInVocabulary(unk_id)));
+            }
+        }
+
+        let token_to_ids = vocab
+            .iter()
+            .enumerate()
+            .map(|(id, (piece, _))| (piece.to_owned(), id as u32))
+            .collect();
+        let min_score = vocab.iter().map(|(_, score)| *score).fold(0.0, f64::min);
+        let max_piece_len = vocab
+            .iter()
+            .map(|(piece, _)| piece.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        Ok(Unigram {
+            vocab,
+            token_to_ids,
+            unk_id,
+            min_score,
+            max_piece_len,
+        })
+    }
+
+    /// Initialize a `Unigram` model from a JSON file, as written by `save`, like
+    /// `{"unk_id": 0, "vocab": [["<unk>", 0.0], ["▁a", -1.2]]}`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    pub fn get_unk_id(&self) -> Option<usize> {
+        self.unk_id
+    }
+
+    /// Find the segmentation of `word` with the highest score, as `(id, start, end, unk)` for
+    /// each piece, with offsets in chars, and whether it is the unk piece of an unknown char.
+    fn encode(&self, word: &str) -> Result<Vec<(u32, usize, usize, bool)>> {
+        // The byte offset of each char, and the end of the word, to slice the pieces directly
+        let bounds = word
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(word.len()))
+            .collect::<Vec<_>>();
+        let n_chars = bounds.len() - 1;
+
+        // For each position, the best score of the chars before it, and the last piece giving
+        // this score, with its start and whether it is unknown.
+        let mut best_scores = vec![f64::NEG_INFINITY; n_chars + 1];
+        let mut best_pieces = vec![(0, 0, false); n_chars + 1];
+        best_scores[0] = 0.0;
+
+        for end in 1..=n_chars {
+            for start in end.saturating_sub(self.max_piece_len)..end {
+                if best_scores[start] == f64::NEG_INFINITY {
+                    continue;
+                }
+                let piece = &word[bounds[start]..bounds[end]];
+                let candidate = match self.token_to_ids.get(piece) {
+                    Some(id) => Some((*id, self.vocab[*id as usize].1, false)),
+                    // An unknown char can only be covered by the unk piece on its own
+                    None if start + 1 == end => self
+                        .unk_id
+                        .map(|unk_id| (unk_id as u32, self.min_score - UNK_PENALTY, true)),
+                    None => None,
+                };
+                if let Some((id, score, unk)) = candidate {
+                    let score = best_scores[start] + score;
+                    if score > best_scores[end] {
+                        best_scores[end] = score;
+                        best_pieces[end] = (id, start, unk);
+                    }
+                }
+            }
+        }
+
+        if best_scores[n_chars] == f64::NEG_INFINITY {
+            return Err(Box::new(Error::MissingUnkId(word.to_owned())));
+        }
+
+        let mut pieces = vec![];
+        let mut end = n_chars;
+        while end > 0 {
+            let (id, start, unk) = best_pieces[end];
+            pieces.push((id, start, end, unk));
+            end = start;
+        }
+        pieces.reverse();
+        Ok(pieces)
+    }
+}
+
+#[typetag::serde]
+impl Model for Unigram {
+    fn tokenize(&self, sentence: Vec<(String, (usize, usize))>) -> Result<Vec<Token>> {
+        let mut tokens = vec![];
+        for (index, (word, initial_offsets)) in sentence.into_iter().enumerate() {
+            for (id, start, end, unk) in self.encode(&word)? {
+                let value = self.vocab[id as usize].0.clone();
+                let offsets = (initial_offsets.0 + start, initial_offsets.0 + end);
+                tokens.push(if unk {
+                    Token::new_unk(id, value, offsets, index as u32)
+                } else {
+                    Token::new(id, value, offsets, index as u32)
+                });
+            }
+        }
+        Ok(tokens)
+    }
+
+    fn token_to_id(&self, token: &str) -> Option<u32> {
+        self.token_to_ids.get(token).copied()
+    }
+
+    fn contains_token(&self, token: &str) -> bool {
+        self.token_to_ids.contains_key(token)
+    }
+
+    fn id_to_token(&self, id: u32) -> Option<&str> {
+        self.vocab.get(id as usize).map(|(piece, _)| piece.as_str())
+    }
+
+    fn get_vocab(&self) -> &HashMap<String, u32> {
+        &self.token_to_ids
+    }
+
+    fn get_vocab_size(&self) -> usize {
+        self.vocab.len()
+    }
+
+    fn save(&self, folder: &Path, name: Option<&str>) -> Result<Vec<PathBuf>> {
+        let file_name = match name {
+            Some(name) => format!("{}-unigram.json", name),
+            None => "unigram.json".to_string(),
+        };
+
+        let path: PathBuf = [folder, Path::new(file_name.as_str())].iter().collect();
+        let mut file = File::create(&path)?;
+        file.write_all(serde_json::to_string(self)?.as_bytes())?;
+
+        Ok(vec![path])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_unigram(unk_id: Option<usize>) -> Unigram {
+        let vocab = vec![
+            ("<unk>", 0.0),
+            ("a", -2.0),
+            ("b", -2.0),
+            ("c", -2.0),
+            ("ab", -3.0),
+            ("bc", -5.0),
+            ("abc", -7.0),
+        ]
+        .into_iter()
+        .map(|(piece, score)| (piece.to_owned(), score))
+        .collect();
+        Unigram::new(vocab, unk_id).unwrap()
+    }
+
+    #[test]
+    fn maximum_likelihood() {
+        let unigram = get_unigram(Some(0));
+        let tokens = |word: &str| {
+            unigram
+                .tokenize(vec![(word.to_owned(), (0, word.chars().count()))])
+                .unwrap()
+                .into_iter()
+                .map(|t| t.value)
+                .collect::<Vec<_>>()
+        };
+
+        // "ab" (-3) beats "a" + "b" (-4), and "ab" + "c" (-5) beats "abc" (-7)
+        assert_eq!(tokens("ab"), ["ab"]);
+        assert_eq!(tokens("abc"), ["ab", "c"]);
+        assert_eq!(tokens("bc"), ["b", "c"]);
+        assert_eq!(tokens(""), Vec::<String>::new());
+    }
+
+    #[test]
+    fn offsets_and_words() {
+        let unigram = get_unigram(Some(0));
+        let tokens = unigram
+            .tokenize(vec![("cab".into(), (0, 3)), ("aéb".into(), (4, 7))])
+            .unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(3, "c".into(), (0, 1), 0),
+                Token::new(4, "ab".into(), (1, 3), 0),
+                Token::new(1, "a".into(), (4, 5), 1),
+                Token::new_unk(0, "<unk>".into(), (5, 6), 1),
+                Token::new(2, "b".into(), (6, 7), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn unk_id() {
+        let unigram = get_unigram(None);
+        let err = unigram.tokenize(vec![("adb".into(), (0, 3))]).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::MissingUnkId(word)) if word == "adb"
+        ));
+
+        let vocab = vec![("a".to_owned(), -1.0)];
+        let err = Unigram::new(vocab, Some(1)).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::UnkIdNotInVocabulary(1))
+        ));
+    }
+
+    #[test]
+    fn save_and_serialization() {
+        let unigram = get_unigram(Some(0));
+
+        let dir = tempfile::tempdir().unwrap();
+        let files = unigram.save(dir.path(), None).unwrap();
+        let reloaded = Unigram::from_file(&files[0]).unwrap();
+        assert_eq!(reloaded.vocab, unigram.vocab);
+        assert_eq!(reloaded.get_unk_id(), Some(0));
+
+        let model: Box<dyn Model> = Box::new(unigram);
+        let serialized = serde_json::to_string(&model).unwrap();
+        assert!(serialized.starts_with(r#"{"type":"Unigram","unk_id":0,"vocab":[["<unk>",0.0],"#));
+        let model: Box<dyn Model> = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(model.token_to_id("bc"), Some(5));
+    }
+}
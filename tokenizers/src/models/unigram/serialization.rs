@@ -0,0 +1,52 @@
+use super::Unigram;
+use serde::{
+    de::{Error, MapAccess, Visitor},
+    ser::SerializeStruct,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+impl Serialize for Unigram {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut model = serializer.serialize_struct("Unigram", 2)?;
+        model.serialize_field("unk_id", &self.unk_id)?;
+        model.serialize_field("vocab", &self.vocab)?;
+        model.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Unigram {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_struct("Unigram", &["unk_id", "vocab"], UnigramVisitor)
+    }
+}
+
+struct UnigramVisitor;
+impl<'de> Visitor<'de> for UnigramVisitor {
+    type Value = Unigram;
+
+    fn expecting(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "struct Unigram")
+    }
+
+    fn visit_map<V>(self, mut map: V) -> std::result::Result<Self::Value, V::Error>
+    where
+        V: MapAccess<'de>,
+    {
+        let mut vocab = vec![];
+        let mut unk_id = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_ref() {
+                "unk_id" => unk_id = map.next_value()?,
+                "vocab" => vocab = map.next_value()?,
+                _ => {}
+            }
+        }
+        Unigram::new(vocab, unk_id).map_err(|e| V::Error::custom(e.to_string()))
+    }
+}
@@ -148,15 +148,17 @@ impl Model for WordLevel {
         let mut output_tokens = vec![];
 
         for (index, (token, initial_offsets)) in tokens.into_iter().enumerate() {
-            let t = Token {
-                id: *self
-                    .vocab
-                    .get(&*token)
-                    .or_else(|| self.vocab.get(&*self.unk_token))
-                    .ok_or(Error::MissingUnkToken)?,
-                value: token,
-                offsets: initial_offsets,
-                word: index as u32,
+            let t = match self.vocab.get(&*token) {
+                Some(id) => Token::new(*id, token, initial_offsets, index as u32),
+                None => Token::new_unk(
+                    *self
+                        .vocab
+                        .get(&*self.unk_token)
+                        .ok_or(Error::MissingUnkToken)?,
+                    token,
+                    initial_offsets,
+                    index as u32,
+                ),
             };
 
             output_tokens.push(t);
@@ -169,6 +171,10 @@ impl Model for WordLevel {
         self.vocab.get(token).copied()
     }
 
+    fn contains_token(&self, token: &str) -> bool {
+        self.vocab.contains_key(token)
+    }
+
     fn id_to_token(&self, id: u32) -> Option<&str> {
         self.vocab_r.get(&id).map(String::as_ref)
     }
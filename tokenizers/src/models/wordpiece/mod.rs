@@ -183,6 +183,12 @@ impl WordPiece {
     }
 
     /// Create a `WordPiece` model from a `BPE` model.
+    ///
+    /// Only the vocabulary, the unknown token and the continuing subword prefix are kept: the
+    /// merges are lost. `WordPiece` can only split a word into subwords that exist in the
+    /// vocabulary with the continuing subword prefix (`##` by default), so a `BPE` that
+    /// doesn't use this prefix in its vocabulary will end up with most words being unknown.
+    /// A warning is printed when the vocabulary has no such continuing subword.
     pub fn from_bpe(bpe: &BPE) -> Self {
         let mut wp = Self::builder()
             .vocab(bpe.get_vocab().clone())
@@ -194,8 +200,28 @@ impl WordPiece {
         if let Some(prefix) = bpe.get_continuing_subword_prefix() {
             wp.continuing_subword_prefix = prefix.to_owned();
         }
+        if let Some(warning) = wp.check_continuing_subwords() {
+            println!("Warning: {}", warning);
+        }
         wp
     }
+
+    /// Check that the vocabulary contains some continuing subwords, returning a warning if not
+    fn check_continuing_subwords(&self) -> Option<String> {
+        if self
+            .vocab
+            .keys()
+            .any(|token| token.starts_with(&self.continuing_subword_prefix))
+        {
+            None
+        } else {
+            Some(format!(
+                "The vocabulary doesn't contain any continuing subword (starting with '{}'), \
+                 most words will be tokenized as '{}'",
+                self.continuing_subword_prefix, self.unk_token
+            ))
+        }
+    }
 }
 
 #[typetag::serde]
@@ -222,6 +248,7 @@ impl Model for WordPiece {
                         .ok_or(Error::MissingUnkToken)?,
                     offsets: initial_offsets,
                     word: index as u32,
+                    unk: true,
                 });
                 continue;
             }
@@ -246,6 +273,7 @@ impl Model for WordPiece {
                             value: substr,
                             offsets: (initial_offsets.0 + start, initial_offsets.0 + end),
                             word: index as u32,
+                            unk: false,
                         });
                         break;
                     }
@@ -270,6 +298,7 @@ impl Model for WordPiece {
                         .ok_or(Error::MissingUnkToken)?,
                     offsets: initial_offsets,
                     word: index as u32,
+                    unk: true,
                 });
             } else {
                 output_tokens.extend(sub_tokens);
@@ -283,6 +312,10 @@ impl Model for WordPiece {
         self.vocab.get(token).copied()
     }
 
+    fn contains_token(&self, token: &str) -> bool {
+        self.vocab.contains_key(token)
+    }
+
     fn id_to_token(&self, id: u32) -> Option<&str> {
         self.vocab_r.get(&id).map(String::as_ref)
     }
@@ -319,4 +352,40 @@ mod tests {
     fn test_error_display() {
         assert!(format!("{}", Error::MissingUnkToken).contains("Missing [UNK] token"));
     }
+
+    #[test]
+    fn from_bpe_without_continuing_subwords() {
+        let vocab: Vocab = [("[UNK]", 0), ("a", 1), ("b", 2), ("ab", 3)]
+            .iter()
+            .map(|(t, id)| (t.to_string(), *id))
+            .collect();
+        let bpe = BPE::builder()
+            .vocab_and_merges(vocab, HashMap::new())
+            .unk_token("[UNK]".into())
+            .build()
+            .unwrap();
+        let wp = WordPiece::from_bpe(&bpe);
+        assert_eq!(
+            wp.check_continuing_subwords(),
+            Some(
+                "The vocabulary doesn't contain any continuing subword (starting with '##'), \
+                 most words will be tokenized as '[UNK]'"
+                    .into()
+            )
+        );
+        // "aab" can't be split, as there is neither "##a" nor "##ab"
+        let tokens = wp.tokenize(vec![("aab".into(), (0, 3))]).unwrap();
+        assert_eq!(tokens, vec![Token::new_unk(0, "[UNK]".into(), (0, 3), 0)]);
+
+        let vocab: Vocab = [("[UNK]", 0), ("a", 1), ("##b", 2)]
+            .iter()
+            .map(|(t, id)| (t.to_string(), *id))
+            .collect();
+        let bpe = BPE::builder()
+            .vocab_and_merges(vocab, HashMap::new())
+            .continuing_subword_prefix("##".into())
+            .build()
+            .unwrap();
+        assert_eq!(WordPiece::from_bpe(&bpe).check_continuing_subwords(), None);
+    }
 }
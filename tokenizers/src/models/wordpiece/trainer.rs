@@ -89,9 +89,30 @@ impl WordPieceTrainer {
         WordPieceTrainerBuilder::default()
     }
 
+    /// Train a `WordPiece` model. On top of the vocabulary learned by the `BpeTrainer`, each
+    /// char of the alphabet is also added with the continuing subword prefix when missing, so
+    /// that any word made of known chars can be tokenized, and decoded back by the `WordPiece`
+    /// decoder. These may get the vocabulary a bit over `vocab_size`.
     pub fn train(&self, word_counts: HashMap<String, u32>) -> Result<(WordPiece, Vec<AddedToken>)> {
         let (bpe, tokens) = self.bpe_trainer.train(word_counts)?;
-        Ok((WordPiece::from_bpe(&bpe), tokens))
+        let mut wp = WordPiece::from_bpe(&bpe);
+
+        let special_tokens = tokens.iter().map(|t| &t.content).collect::<HashSet<_>>();
+        let mut missing = wp
+            .vocab
+            .keys()
+            .filter(|token| token.chars().count() == 1 && !special_tokens.contains(token))
+            .map(|c| format!("{}{}", wp.continuing_subword_prefix, c))
+            .filter(|token| !wp.vocab.contains_key(token))
+            .collect::<Vec<_>>();
+        missing.sort();
+        for token in missing {
+            let id = wp.vocab.len() as u32;
+            wp.vocab.insert(token.clone(), id);
+            wp.vocab_r.insert(id, token);
+        }
+
+        Ok((wp, tokens))
     }
 }
 
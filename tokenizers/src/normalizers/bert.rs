@@ -1,6 +1,7 @@
 use crate::tokenizer::{NormalizedString, Normalizer, Result};
 use serde::{Deserialize, Serialize};
 use unicode_categories::UnicodeCategories;
+use unicode_normalization_alignments::UnicodeNormalization;
 
 /// Checks whether a character is whitespace
 fn is_whitespace(c: char) -> bool {
@@ -48,6 +49,49 @@ fn is_chinese_char(c: char) -> bool {
     }
 }
 
+/// The precomposed Vietnamese letters, with a diacritic or a tone mark
+const VIETNAMESE: &str = "àáảãạăằắẳẵặâầấẩẫậèéẻẽẹêềếểễệìíỉĩịòóỏõọôồốổỗộơờớởỡợùúủũụưừứửữựỳýỷỹỵ\
+                          ÀÁẢÃẠĂẰẮẲẴẶÂẦẤẨẪẬÈÉẺẼẸÊỀẾỂỄỆÌÍỈĨỊÒÓỎÕỌÔỒỐỔỖỘƠỜỚỞỠỢÙÚỦŨỤƯỪỨỬỮỰỲÝỶỸỴ";
+
+/// A set of letters that can keep their accents, when the `BertNormalizer` strips them from
+/// everything else. Most of them are Unicode blocks, but a language like Vietnamese spans
+/// several blocks and shares some of its letters with other languages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Script {
+    /// Latin-1 Supplement (U+0080 to U+00FF), with the accented letters of western European
+    /// languages
+    Latin1Supplement,
+    /// Latin Extended-A (U+0100 to U+017F)
+    LatinExtendedA,
+    /// Latin Extended-B (U+0180 to U+024F)
+    LatinExtendedB,
+    /// Latin Extended Additional (U+1E00 to U+1EFF)
+    LatinExtendedAdditional,
+    /// Greek and Coptic (U+0370 to U+03FF)
+    Greek,
+    /// Cyrillic (U+0400 to U+04FF)
+    Cyrillic,
+    /// The Vietnamese letters, spread over Latin-1 Supplement, Latin Extended-A and B, and
+    /// Latin Extended Additional. Note that this includes some letters like `é` or `à`.
+    Vietnamese,
+}
+
+impl Script {
+    /// Whether the given precomposed letter is part of this script
+    pub fn contains(self, c: char) -> bool {
+        let range = match self {
+            Script::Vietnamese => return VIETNAMESE.contains(c),
+            Script::Latin1Supplement => 0x0080..=0x00FF,
+            Script::LatinExtendedA => 0x0100..=0x017F,
+            Script::LatinExtendedB => 0x0180..=0x024F,
+            Script::LatinExtendedAdditional => 0x1E00..=0x1EFF,
+            Script::Greek => 0x0370..=0x03FF,
+            Script::Cyrillic => 0x0400..=0x04FF,
+        };
+        range.contains(&(c as u32))
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct BertNormalizer {
     /// Whether to do the bert basic cleaning:
@@ -60,6 +104,10 @@ pub struct BertNormalizer {
     strip_accents: Option<bool>,
     /// Whether to lowercase the input
     lowercase: bool,
+    /// The scripts whose letters keep their accents when stripping them. A letter written
+    /// with some combining marks is kept too, and recomposed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    keep_accents_scripts: Vec<Script>,
 }
 
 impl Default for BertNormalizer {
@@ -69,6 +117,7 @@ impl Default for BertNormalizer {
             handle_chinese_chars: true,
             strip_accents: None,
             lowercase: true,
+            keep_accents_scripts: vec![],
         }
     }
 }
@@ -85,9 +134,16 @@ impl BertNormalizer {
             handle_chinese_chars,
             strip_accents,
             lowercase,
+            keep_accents_scripts: vec![],
         }
     }
 
+    /// Keep the accents of the letters from the given scripts, when stripping accents
+    pub fn keep_accents_scripts(mut self, scripts: Vec<Script>) -> Self {
+        self.keep_accents_scripts = scripts;
+        self
+    }
+
     fn do_clean_text(&self, normalized: &mut NormalizedString) {
         normalized
             .filter(|c| !(*c as usize == 0 || *c as usize == 0xfffd || is_control(*c)))
@@ -107,7 +163,44 @@ impl BertNormalizer {
     }
 
     fn do_strip_accents(&self, normalized: &mut NormalizedString) {
-        normalized.nfd().filter(|c| !c.is_mark_nonspacing());
+        normalized.nfd();
+        if self.keep_accents_scripts.is_empty() {
+            normalized.filter(|c| !c.is_mark_nonspacing());
+            return;
+        }
+
+        // Each base char comes with the marks following it: we recompose them when this gives
+        // a kept letter, and keep only the base char otherwise. Any mark at the very beginning
+        // has no base char, so it gets removed.
+        let chars = normalized.get().chars().collect::<Vec<_>>();
+        let leading = chars.iter().take_while(|c| c.is_mark_nonspacing()).count();
+        let mut new_chars: Vec<(char, isize)> = vec![];
+        let mut start = leading;
+        while start < chars.len() {
+            let marks = chars[start + 1..]
+                .iter()
+                .take_while(|c| c.is_mark_nonspacing())
+                .count();
+            let end = start + 1 + marks;
+            let composed = chars[start..end]
+                .iter()
+                .collect::<String>()
+                .nfc()
+                .collect::<Vec<_>>();
+            let kept = marks > 0
+                && composed.iter().all(|(c, _)| {
+                    self.keep_accents_scripts
+                        .iter()
+                        .any(|script| script.contains(*c))
+                });
+            if kept {
+                new_chars.extend(composed);
+            } else {
+                new_chars.push((chars[start], -(marks as isize)));
+            }
+            start = end;
+        }
+        normalized.transform(new_chars.into_iter(), leading);
     }
 
     fn do_lowercase(&self, normalized: &mut NormalizedString) {
@@ -118,13 +211,18 @@ impl BertNormalizer {
 #[typetag::serde]
 impl Normalizer for BertNormalizer {
     fn normalize(&self, mut normalized: &mut NormalizedString) -> Result<()> {
+        let strip_accents = self.strip_accents.unwrap_or(self.lowercase);
+        if !(self.clean_text || self.handle_chinese_chars || strip_accents || self.lowercase) {
+            // Nothing to do, we don't even need to look at the string
+            return Ok(());
+        }
+
         if self.clean_text {
             self.do_clean_text(&mut normalized);
         }
         if self.handle_chinese_chars {
             self.do_handle_chinese_chars(&mut normalized);
         }
-        let strip_accents = self.strip_accents.unwrap_or(self.lowercase);
         if strip_accents {
             self.do_strip_accents(&mut normalized);
         }
@@ -135,3 +233,69 @@ impl Normalizer for BertNormalizer {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Range;
+
+    #[test]
+    fn disabled_is_identity() {
+        let normalizer = BertNormalizer::new(false, false, None, false);
+        let input = "Héllo\tthere 中 \u{0}!";
+        let mut normalized = NormalizedString::from(input);
+        normalizer.normalize(&mut normalized).unwrap();
+        assert_eq!(normalized, NormalizedString::from(input));
+
+        // Stripping accents alone is still applied
+        let normalizer = BertNormalizer::new(false, false, Some(true), false);
+        let mut normalized = NormalizedString::from(input);
+        normalizer.normalize(&mut normalized).unwrap();
+        assert_eq!(normalized.get(), "Hello\tthere 中 \u{0}!");
+    }
+
+    #[test]
+    fn keep_accents_scripts() {
+        let input = "Café phở Tiếng Việt";
+        let normalizer = BertNormalizer::new(false, false, Some(true), false);
+        let mut normalized = NormalizedString::from(input);
+        normalizer.normalize(&mut normalized).unwrap();
+        assert_eq!(normalized.get(), "Cafe pho Tieng Viet");
+
+        let normalizer = BertNormalizer::new(false, false, Some(true), false)
+            .keep_accents_scripts(vec![Script::LatinExtendedAdditional]);
+        let mut normalized = NormalizedString::from(input);
+        normalizer.normalize(&mut normalized).unwrap();
+        assert_eq!(normalized.get(), "Cafe phở Tiếng Việt");
+        assert_eq!(
+            normalized.get_range_original(Range::Normalized(9..15)),
+            Some("Tiếng ")
+        );
+    }
+
+    #[test]
+    fn keep_accents_vietnamese() {
+        let input = "Ça va? Tôi là người Việt";
+        let normalizer = BertNormalizer::new(false, false, Some(true), false)
+            .keep_accents_scripts(vec![Script::Vietnamese]);
+        let mut normalized = NormalizedString::from(input);
+        normalizer.normalize(&mut normalized).unwrap();
+        assert_eq!(normalized.get(), "Ca va? Tôi là người Việt");
+        assert_eq!(
+            normalized.get_range_original(Range::Normalized(7..10)),
+            Some("Tôi")
+        );
+
+        // The decomposed input gives the same letters
+        let decomposed = input.nfd().map(|(c, _)| c).collect::<String>();
+        assert_ne!(decomposed, input);
+        let mut normalized = NormalizedString::from(decomposed.as_str());
+        normalizer.normalize(&mut normalized).unwrap();
+        assert_eq!(normalized.get(), "Ca va? Tôi là người Việt");
+
+        // Even with a leading mark that has no base char
+        let mut normalized = NormalizedString::from("\u{0301}Việt");
+        normalizer.normalize(&mut normalized).unwrap();
+        assert_eq!(normalized.get(), "Việt");
+    }
+}
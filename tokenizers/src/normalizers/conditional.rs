@@ -0,0 +1,91 @@
+use crate::tokenizer::{NormalizedString, Normalizer, Result};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
+
+#[derive(Serialize)]
+/// Runs the given normalizer only when the normalized string matches `predicate_regex`.
+/// Offsets are left untouched when there is no match.
+pub struct Conditional {
+    predicate_regex: String,
+    normalizer: Box<dyn Normalizer>,
+    #[serde(skip)]
+    regex: Regex,
+}
+
+impl Conditional {
+    pub fn new(predicate_regex: &str, normalizer: Box<dyn Normalizer>) -> Result<Self> {
+        Ok(Self {
+            predicate_regex: predicate_regex.to_owned(),
+            normalizer,
+            regex: Regex::new(predicate_regex)?,
+        })
+    }
+}
+
+#[typetag::serde]
+impl Normalizer for Conditional {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        if self.regex.is_match(normalized.get()) {
+            self.normalizer.normalize(normalized)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for Conditional {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct ConditionalConfig {
+            predicate_regex: String,
+            normalizer: Box<dyn Normalizer>,
+        }
+
+        let config = ConditionalConfig::deserialize(deserializer)?;
+        Conditional::new(&config.predicate_regex, config.normalizer)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::normalizers::utils::Lowercase;
+    use crate::tokenizer::Range;
+
+    #[test]
+    fn only_when_matching() {
+        let normalizer = Conditional::new(r"\p{Han}", Box::new(Lowercase)).unwrap();
+
+        let mut n = NormalizedString::from("Hello 世界");
+        normalizer.normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "hello 世界");
+
+        let mut n = NormalizedString::from("Hello World");
+        normalizer.normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "Hello World");
+        assert_eq!(n.convert_offsets(Range::Normalized(0..5)), Some(0..5));
+    }
+
+    #[test]
+    fn serialization() {
+        let normalizer: Box<dyn Normalizer> =
+            Box::new(Conditional::new(r"\p{Han}", Box::new(Lowercase)).unwrap());
+        let serialized = serde_json::to_string(&normalizer).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"Conditional","predicate_regex":"\\p{Han}","normalizer":{"type":"Lowercase"}}"#
+        );
+
+        let normalizer: Box<dyn Normalizer> = serde_json::from_str(&serialized).unwrap();
+        let mut n = NormalizedString::from("Hello 世界");
+        normalizer.normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "hello 世界");
+
+        let invalid =
+            r#"{"type":"Conditional","predicate_regex":"(","normalizer":{"type":"Lowercase"}}"#;
+        assert!(serde_json::from_str::<Box<dyn Normalizer>>(invalid).is_err());
+    }
+}
@@ -1,4 +1,10 @@
 pub mod bert;
+pub mod conditional;
+pub mod replace;
 pub mod strip;
 pub mod unicode;
 pub mod utils;
+pub mod whitespace;
+
+// The `Sequence` of normalizers is also available from here
+pub use utils::Sequence;
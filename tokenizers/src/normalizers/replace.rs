@@ -0,0 +1,161 @@
+use crate::tokenizer::{NormalizedString, Normalizer, Result};
+use regex::Regex;
+use serde::{ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
+
+/// The pattern of a `Replace`, either a literal string or a regex. The regex is given by its
+/// pattern only, so any flag must be set inline, like `(?i)` for case insensitivity.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ReplacePattern {
+    String(String),
+    Regex(String),
+}
+
+impl From<&str> for ReplacePattern {
+    fn from(pattern: &str) -> Self {
+        ReplacePattern::String(pattern.to_owned())
+    }
+}
+
+impl From<String> for ReplacePattern {
+    fn from(pattern: String) -> Self {
+        ReplacePattern::String(pattern)
+    }
+}
+
+/// Replaces each occurrence of the given pattern with some content, like `\t` with a space.
+/// With a regex pattern, each of its non-empty matches gets replaced.
+pub struct Replace {
+    pattern: ReplacePattern,
+    content: String,
+    /// The compiled `pattern`, when it is a regex
+    regex: Option<Regex>,
+}
+
+impl Replace {
+    /// Create a `Replace`, failing if the pattern is an invalid regex
+    pub fn new<P: Into<ReplacePattern>, C: Into<String>>(pattern: P, content: C) -> Result<Self> {
+        let pattern = pattern.into();
+        let regex = match &pattern {
+            ReplacePattern::String(_) => None,
+            ReplacePattern::Regex(regex) => Some(Regex::new(regex)?),
+        };
+
+        Ok(Self {
+            pattern,
+            content: content.into(),
+            regex,
+        })
+    }
+}
+
+impl Serialize for Replace {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut replace = serializer.serialize_struct("Replace", 2)?;
+        replace.serialize_field("pattern", &self.pattern)?;
+        replace.serialize_field("content", &self.content)?;
+        replace.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Replace {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // The first versions only supported a literal string, serialized as is
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum UncompiledPattern {
+            Pattern(ReplacePattern),
+            String(String),
+        }
+
+        #[derive(Deserialize)]
+        struct Uncompiled {
+            pattern: UncompiledPattern,
+            content: String,
+        }
+
+        let Uncompiled { pattern, content } = Uncompiled::deserialize(deserializer)?;
+        let pattern = match pattern {
+            UncompiledPattern::Pattern(pattern) => pattern,
+            UncompiledPattern::String(pattern) => ReplacePattern::String(pattern),
+        };
+        Replace::new(pattern, content).map_err(serde::de::Error::custom)
+    }
+}
+
+#[typetag::serde]
+impl Normalizer for Replace {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        match (&self.pattern, &self.regex) {
+            (_, Some(regex)) => normalized.replace_regex(regex, &self.content),
+            (ReplacePattern::String(pattern), None) => normalized.replace(pattern, &self.content),
+            (ReplacePattern::Regex(_), None) => unreachable!("The regex is compiled in `new`"),
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Range;
+
+    #[test]
+    fn replace_string() {
+        let mut n = NormalizedString::from("``Hi``, he said");
+        Replace::new("``", "\"").unwrap().normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "\"Hi\", he said");
+        assert_eq!(n.convert_offsets(Range::Normalized(1..3)), Some(2..4));
+        assert_eq!(n.convert_offsets(Range::Normalized(3..4)), Some(4..5));
+        assert_eq!(n.get_range_original(Range::Normalized(4..5)), Some(","));
+    }
+
+    #[test]
+    fn replace_regex() {
+        let pattern = ReplacePattern::Regex(r"&(amp|lt|gt);".into());
+        let replace = Replace::new(pattern, "&").unwrap();
+        let mut n = NormalizedString::from("a &lt;&amp; b");
+        replace.normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "a && b");
+        assert_eq!(n.get_range_original(Range::Normalized(5..6)), Some("b"));
+
+        assert!(Replace::new(ReplacePattern::Regex("(".into()), "").is_err());
+    }
+
+    #[test]
+    fn serialization() {
+        let replace: Box<dyn Normalizer> = Box::new(Replace::new("``", "\"").unwrap());
+        assert_eq!(
+            serde_json::to_string(&replace).unwrap(),
+            r#"{"type":"Replace","pattern":{"String":"``"},"content":"\""}"#
+        );
+
+        let serialized = r#"{"type":"Replace","pattern":{"Regex":"\\s+"},"content":" "}"#;
+        let replace: Box<dyn Normalizer> = serde_json::from_str(serialized).unwrap();
+        let mut n = NormalizedString::from("a \t\nb");
+        replace.normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "a b");
+        assert_eq!(serde_json::to_string(&replace).unwrap(), serialized);
+
+        let invalid = r#"{"type":"Replace","pattern":{"Regex":"("},"content":" "}"#;
+        assert!(serde_json::from_str::<Box<dyn Normalizer>>(invalid).is_err());
+    }
+
+    #[test]
+    fn deserialize_string_pattern() {
+        let serialized = r#"{"type":"Replace","pattern":"\t","content":" "}"#;
+        let replace: Box<dyn Normalizer> = serde_json::from_str(serialized).unwrap();
+        let mut n = NormalizedString::from("a\tb");
+        replace.normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "a b");
+        assert_eq!(
+            serde_json::to_string(&replace).unwrap(),
+            r#"{"type":"Replace","pattern":{"String":"\t"},"content":" "}"#
+        );
+    }
+}
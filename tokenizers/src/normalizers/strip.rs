@@ -36,3 +36,32 @@ impl Normalizer for Strip {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Range;
+
+    #[test]
+    fn strip_only_whitespaces() {
+        let mut n = NormalizedString::from("   ");
+        Strip::new(true, true).normalize(&mut n).unwrap();
+
+        assert_eq!(n.get(), "");
+        assert_eq!(n.len(), 0);
+        assert_eq!(n.len_original(), 3);
+        assert_eq!(n.get_original(), "   ");
+        // Nothing in the original string maps to the normalized one anymore
+        assert_eq!(n.convert_offsets(Range::Original(0..3)), Some(0..0));
+        assert_eq!(n.convert_offsets(Range::Normalized(0..0)), None);
+    }
+
+    #[test]
+    fn strip_left_only_whitespaces() {
+        let mut n = NormalizedString::from("   ");
+        Strip::new(true, false).normalize(&mut n).unwrap();
+
+        assert_eq!(n.get(), "");
+        assert_eq!(n.len_original(), 3);
+    }
+}
@@ -40,3 +40,67 @@ impl Normalizer for NFKC {
         Ok(())
     }
 }
+
+#[derive(Serialize, Deserialize)]
+/// Applies NFKC and then case folds, to compare strings regardless of their case and of
+/// their compatibility forms, like for search or fuzzy matching
+pub struct CaseFold;
+#[typetag::serde]
+impl Normalizer for CaseFold {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        normalized.nfkc().case_fold();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Range;
+
+    fn case_fold(s: &str) -> NormalizedString {
+        let mut normalized = NormalizedString::from(s);
+        CaseFold.normalize(&mut normalized).unwrap();
+        normalized
+    }
+
+    #[test]
+    fn case_fold_sharp_s() {
+        let normalized = case_fold("Straße");
+        assert_eq!(normalized.get(), "strasse");
+        assert_eq!(
+            normalized.convert_offsets(Range::Normalized(4..6)),
+            Some(4..5)
+        );
+        assert_eq!(
+            normalized.convert_offsets(Range::Normalized(6..7)),
+            Some(5..6)
+        );
+        assert_eq!(case_fold("STRASSE").get(), "strasse");
+    }
+
+    #[test]
+    fn case_fold_ligature() {
+        let normalized = case_fold("ﬁnE");
+        assert_eq!(normalized.get(), "fine");
+        assert_eq!(
+            normalized.convert_offsets(Range::Normalized(0..2)),
+            Some(0..1)
+        );
+        assert_eq!(
+            normalized.convert_offsets(Range::Normalized(2..4)),
+            Some(1..3)
+        );
+    }
+
+    #[test]
+    fn case_fold_final_sigma() {
+        let normalized = case_fold("σας");
+        assert_eq!(normalized.get(), "σασ");
+        assert_eq!(
+            normalized.convert_offsets(Range::Normalized(2..3)),
+            Some(2..3)
+        );
+        assert_eq!(case_fold("ΣΑΣ").get(), "σασ");
+    }
+}
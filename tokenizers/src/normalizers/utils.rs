@@ -34,3 +34,57 @@ impl Normalizer for Lowercase {
         Ok(())
     }
 }
+
+#[derive(Serialize, Deserialize)]
+/// Removes the control characters (like `\x00` or `\x07`) from the input, or replaces them
+/// with the given `replacement` char. The whitespace ones, like `\t` or `\n`, are kept.
+pub struct ControlChars {
+    replacement: Option<char>,
+}
+
+impl ControlChars {
+    /// Remove the control characters
+    pub fn remove() -> Self {
+        Self { replacement: None }
+    }
+
+    /// Replace each control character with the given char
+    pub fn replace(replacement: char) -> Self {
+        Self {
+            replacement: Some(replacement),
+        }
+    }
+}
+
+#[typetag::serde]
+impl Normalizer for ControlChars {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        let is_control = |c: char| c.is_control() && !c.is_whitespace();
+        match self.replacement {
+            None => normalized.filter(|c| !is_control(*c)),
+            Some(replacement) => normalized.map(|c| if is_control(c) { replacement } else { c }),
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Range;
+
+    #[test]
+    fn control_chars() {
+        let mut n = NormalizedString::from("ab\x07 c\td");
+        ControlChars::remove().normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "ab c\td");
+        assert_eq!(n.convert_offsets(Range::Normalized(3..4)), Some(4..5));
+        assert_eq!(n.get_range_original(Range::Normalized(4..5)), Some("\t"));
+
+        let mut n = NormalizedString::from("ab\x07 cd");
+        ControlChars::replace('?').normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "ab? cd");
+        assert_eq!(n.convert_offsets(Range::Normalized(2..3)), Some(2..3));
+        assert_eq!(n.convert_offsets(Range::Normalized(4..5)), Some(4..5));
+    }
+}
@@ -0,0 +1,49 @@
+use crate::tokenizer::{NormalizedString, Normalizer, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+/// Replaces each run of whitespace with a single space, aligned with the first whitespace
+/// of the run.
+pub struct CollapseWhitespace;
+
+#[typetag::serde]
+impl Normalizer for CollapseWhitespace {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        let mut new_chars: Vec<(char, isize)> = vec![];
+        let mut in_whitespace = false;
+        for c in normalized.get().chars() {
+            if !c.is_whitespace() {
+                new_chars.push((c, 0));
+                in_whitespace = false;
+            } else if in_whitespace {
+                // Removed, right after the space we keep for the whole run
+                new_chars.last_mut().unwrap().1 -= 1;
+            } else {
+                new_chars.push((' ', 0));
+                in_whitespace = true;
+            }
+        }
+        normalized.transform(new_chars.into_iter(), 0);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Range;
+
+    #[test]
+    fn collapse() {
+        let mut n = NormalizedString::from("a   b");
+        CollapseWhitespace.normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "a b");
+        assert_eq!(n.convert_offsets(Range::Normalized(2..3)), Some(4..5));
+        assert_eq!(n.convert_offsets(Range::Normalized(1..2)), Some(1..2));
+
+        let mut n = NormalizedString::from("\t\n é \r\n");
+        CollapseWhitespace.normalize(&mut n).unwrap();
+        assert_eq!(n.get(), " é ");
+        assert_eq!(n.convert_offsets(Range::Normalized(1..2)), Some(3..4));
+    }
+}
@@ -34,7 +34,7 @@ lazy_static! {
         Regex::new(r"'s|'t|'re|'ve|'m|'ll|'d| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+(?!\S)|\s+")
             .unwrap();
     static ref BYTES_CHAR: HashMap<u8, char> = bytes_char();
-    static ref CHAR_BYTES: HashMap<char, u8> =
+    pub(crate) static ref CHAR_BYTES: HashMap<char, u8> =
         bytes_char().into_iter().map(|(c, b)| (b, c)).collect();
 }
 
@@ -42,6 +42,11 @@ lazy_static! {
 /// Provides all the necessary steps to handle the BPE tokenization at the byte-level. Takes care
 /// of all the required processing steps to transform a UTF-8 string as needed before and after the
 /// BPE model does its job.
+///
+/// The offsets returned by `pre_tokenize` are relative to the byte-level representation, and can
+/// be converted back to the original input with `NormalizedString::convert_offsets`. A byte-level
+/// char represents a single byte, so any offsets covering only part of a multi-bytes char are
+/// expanded to include the whole char.
 pub struct ByteLevel {
     /// Whether to add a leading space to the first word. This allows to treat the leading word
     /// just as any other word.
@@ -87,7 +92,25 @@ impl ByteLevel {
 #[typetag::serde]
 impl PreTokenizer for ByteLevel {
     fn pre_tokenize(&self, normalized: &mut NormalizedString) -> Result<Vec<(String, Offsets)>> {
-        if self.add_prefix_space && !normalized.get().starts_with(' ') {
+        self.split(normalized, self.add_prefix_space)
+    }
+
+    /// The prefix space is only added to the first piece of a pre-tokenized sequence
+    fn pre_tokenize_continuation(
+        &self,
+        normalized: &mut NormalizedString,
+    ) -> Result<Vec<(String, Offsets)>> {
+        self.split(normalized, false)
+    }
+}
+
+impl ByteLevel {
+    fn split(
+        &self,
+        normalized: &mut NormalizedString,
+        add_prefix_space: bool,
+    ) -> Result<Vec<(String, Offsets)>> {
+        if add_prefix_space && !normalized.get().starts_with(' ') {
             normalized.prepend(" ");
         }
 
@@ -246,7 +269,7 @@ pub fn process_offsets(encoding: &mut Encoding, add_prefix_space: bool) {
 mod tests {
     use super::ByteLevel;
     use crate::tokenizer::{
-        Decoder, Encoding, NormalizedString, PostProcessor, PreTokenizer, Range,
+        get_range_of, Decoder, Encoding, NormalizedString, PostProcessor, PreTokenizer, Range,
     };
 
     #[test]
@@ -390,6 +413,35 @@ mod tests {
         assert_eq!(input.get_range_original(Range::Normalized(1..4)), Some("⭢"));
     }
 
+    #[test]
+    fn offsets_back_to_original() {
+        let mut input = NormalizedString::from("Hello world i⭢j");
+        let bytelevel = ByteLevel::default().add_prefix_space(false);
+        let p = bytelevel.pre_tokenize(&mut input).unwrap();
+
+        let original = p
+            .iter()
+            .map(|(_, offsets)| {
+                let range = input
+                    .convert_offsets(Range::Normalized(offsets.0..offsets.1))
+                    .unwrap();
+                get_range_of(input.get_original(), range).unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(original, vec!["Hello", " world", " i", "⭢", "j"]);
+
+        // A piece of a multi-bytes char maps to the whole char
+        let (start, end) = p[3].1;
+        assert_eq!(
+            input.convert_offsets(Range::Normalized(start..start + 1)),
+            Some(13..14)
+        );
+        assert_eq!(
+            input.convert_offsets(Range::Normalized(start..end)),
+            Some(13..14)
+        );
+    }
+
     #[test]
     fn processor_trims_offsets() {
         let start = Encoding::new(
@@ -1,3 +1,4 @@
+pub use crate::tokenizer::SplitDelimiterBehavior;
 use crate::tokenizer::{NormalizedString, Offsets, PreTokenizer, Result};
 use serde::{Deserialize, Serialize};
 
@@ -38,3 +39,81 @@ impl PreTokenizer for CharDelimiterSplit {
         Ok(words)
     }
 }
+
+/// Splits on any of the given string delimiters. When several delimiters match at the
+/// same position, the longest one wins, so `["\r\n", "\n"]` keeps `"\r\n"` as a single
+/// delimiter instead of splitting it.
+#[derive(Serialize, Deserialize)]
+pub struct StringDelimiterSplit {
+    delimiters: Vec<String>,
+    #[serde(default)]
+    behavior: SplitDelimiterBehavior,
+}
+
+impl StringDelimiterSplit {
+    pub fn new(delimiters: Vec<String>, behavior: SplitDelimiterBehavior) -> Self {
+        StringDelimiterSplit {
+            delimiters: delimiters.into_iter().filter(|d| !d.is_empty()).collect(),
+            behavior,
+        }
+    }
+
+    /// Splits on `"\r\n"` and `"\n"`, keeping each newline as its own piece
+    pub fn newlines() -> Self {
+        Self::new(
+            vec!["\r\n".into(), "\n".into()],
+            SplitDelimiterBehavior::Isolated,
+        )
+    }
+}
+
+#[typetag::serde]
+impl PreTokenizer for StringDelimiterSplit {
+    fn pre_tokenize(&self, normalized: &mut NormalizedString) -> Result<Vec<(String, Offsets)>> {
+        Ok(normalized
+            .split(&self.delimiters[..], self.behavior)
+            .into_iter()
+            .map(|(piece, offsets)| (piece.get().to_owned(), offsets))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newlines_isolated() {
+        let pretok = StringDelimiterSplit::newlines();
+        let mut input = NormalizedString::from("a\nb\r\nc");
+        let res = pretok.pre_tokenize(&mut input).unwrap();
+        assert_eq!(
+            res,
+            vec![
+                ("a".into(), (0, 1)),
+                ("\n".into(), (1, 2)),
+                ("b".into(), (2, 3)),
+                ("\r\n".into(), (3, 5)),
+                ("c".into(), (5, 6)),
+            ]
+        );
+    }
+
+    #[test]
+    fn delimiters_removed() {
+        let pretok = StringDelimiterSplit::new(
+            vec!["\r\n".into(), "\n".into()],
+            SplitDelimiterBehavior::Removed,
+        );
+        let mut input = NormalizedString::from("é\n\nb\r\nc\n");
+        let res = pretok.pre_tokenize(&mut input).unwrap();
+        assert_eq!(
+            res,
+            vec![
+                ("é".into(), (0, 1)),
+                ("b".into(), (3, 4)),
+                ("c".into(), (6, 7)),
+            ]
+        );
+    }
+}
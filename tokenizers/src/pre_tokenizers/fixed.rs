@@ -0,0 +1,71 @@
+use crate::tokenizer::{NormalizedString, Offsets, PreTokenizer, Result};
+use serde::{Deserialize, Serialize};
+
+/// Splits the normalized string in consecutive pieces of `n_chars` chars, whatever their
+/// content. The last piece may be shorter. With `n_chars` set to 0, the whole string is
+/// kept as a single piece.
+#[derive(Serialize, Deserialize)]
+pub struct FixedWidth {
+    n_chars: usize,
+}
+
+impl FixedWidth {
+    pub fn new(n_chars: usize) -> Self {
+        FixedWidth { n_chars }
+    }
+}
+
+#[typetag::serde]
+impl PreTokenizer for FixedWidth {
+    fn pre_tokenize(&self, normalized: &mut NormalizedString) -> Result<Vec<(String, Offsets)>> {
+        if self.n_chars == 0 {
+            return Ok(vec![(normalized.get().to_owned(), (0, normalized.len()))]);
+        }
+
+        let chars = normalized.get().chars().collect::<Vec<_>>();
+        Ok(chars
+            .chunks(self.n_chars)
+            .enumerate()
+            .map(|(i, chunk)| {
+                let start = i * self.n_chars;
+                (chunk.iter().collect(), (start, start + chunk.len()))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_width() {
+        let pretok = FixedWidth::new(2);
+        let mut input = NormalizedString::from("abcdef");
+        let res = pretok.pre_tokenize(&mut input).unwrap();
+        assert_eq!(
+            res,
+            vec![
+                ("ab".into(), (0, 2)),
+                ("cd".into(), (2, 4)),
+                ("ef".into(), (4, 6)),
+            ]
+        );
+
+        // Windows count chars, not bytes
+        let mut input = NormalizedString::from("àbçde");
+        let res = pretok.pre_tokenize(&mut input).unwrap();
+        assert_eq!(
+            res,
+            vec![
+                ("àb".into(), (0, 2)),
+                ("çd".into(), (2, 4)),
+                ("e".into(), (4, 5)),
+            ]
+        );
+
+        let pretok = FixedWidth::new(0);
+        let res = pretok.pre_tokenize(&mut input).unwrap();
+        assert_eq!(res, vec![("àbçde".into(), (0, 5))]);
+    }
+}
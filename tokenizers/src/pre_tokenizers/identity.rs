@@ -0,0 +1,32 @@
+use crate::tokenizer::{NormalizedString, Offsets, PreTokenizer, Result};
+use serde::{Deserialize, Serialize};
+
+/// Keeps the whole normalized string as a single word, so that the model sees all of it. This
+/// is what happens without any `PreTokenizer`, but makes it explicit in a configuration, or in
+/// a sequence of pre-tokenizers.
+#[derive(Serialize, Deserialize)]
+pub struct Identity;
+
+#[typetag::serde]
+impl PreTokenizer for Identity {
+    fn pre_tokenize(&self, normalized: &mut NormalizedString) -> Result<Vec<(String, Offsets)>> {
+        Ok(vec![(normalized.get().to_owned(), (0, normalized.len()))])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whole_string() {
+        let pretok = Identity;
+        let mut input = NormalizedString::from("Hey friend! ❤️");
+        let res = pretok.pre_tokenize(&mut input).unwrap();
+        assert_eq!(res, vec![("Hey friend! ❤️".into(), (0, 14))]);
+
+        let serialized = serde_json::to_string(&pretok as &dyn PreTokenizer).unwrap();
+        assert_eq!(serialized, r#"{"type":"Identity"}"#);
+        assert!(serde_json::from_str::<Box<dyn PreTokenizer>>(&serialized).is_ok());
+    }
+}
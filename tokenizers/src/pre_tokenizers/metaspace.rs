@@ -1,3 +1,4 @@
+use crate::decoders::metaspace::MetaspaceDecoder;
 use crate::tokenizer::{Decoder, NormalizedString, Offsets, PreTokenizer, Result};
 use serde::{Deserialize, Serialize};
 
@@ -16,6 +17,15 @@ impl Metaspace {
             add_prefix_space,
         }
     }
+
+    pub fn get_replacement(&self) -> char {
+        self.replacement
+    }
+
+    /// The decoder matching this pre-tokenizer's configuration
+    pub fn decoder(&self) -> MetaspaceDecoder {
+        MetaspaceDecoder::new(self.replacement, self.add_prefix_space)
+    }
 }
 
 impl Default for Metaspace {
@@ -27,7 +37,25 @@ impl Default for Metaspace {
 #[typetag::serde]
 impl PreTokenizer for Metaspace {
     fn pre_tokenize(&self, normalized: &mut NormalizedString) -> Result<Vec<(String, Offsets)>> {
-        if self.add_prefix_space && !normalized.get().starts_with(' ') {
+        self.split(normalized, self.add_prefix_space)
+    }
+
+    /// The prefix space is only added to the first piece of a pre-tokenized sequence
+    fn pre_tokenize_continuation(
+        &self,
+        normalized: &mut NormalizedString,
+    ) -> Result<Vec<(String, Offsets)>> {
+        self.split(normalized, false)
+    }
+}
+
+impl Metaspace {
+    fn split(
+        &self,
+        normalized: &mut NormalizedString,
+        add_prefix_space: bool,
+    ) -> Result<Vec<(String, Offsets)>> {
+        if add_prefix_space && !normalized.get().starts_with(' ') {
             normalized.prepend(" ");
         }
 
@@ -58,24 +86,11 @@ impl PreTokenizer for Metaspace {
 #[typetag::serde]
 impl Decoder for Metaspace {
     fn decode(&self, tokens: Vec<String>) -> Result<String> {
-        Ok(tokens
-            .iter()
-            .flat_map(|t| t.chars())
-            .enumerate()
-            .map(|(i, c)| {
-                if c == self.replacement {
-                    if i == 0 && self.add_prefix_space {
-                        None
-                    } else {
-                        Some(' ')
-                    }
-                } else {
-                    Some(c)
-                }
-            })
-            .filter(|c| c.is_some())
-            .map(|c| c.unwrap())
-            .collect::<String>())
+        self.decoder().decode(tokens)
+    }
+
+    fn decode_groups(&self, groups: Vec<Vec<String>>) -> Result<String> {
+        self.decoder().decode_groups(groups)
     }
 }
 
@@ -110,6 +125,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn tabs_and_newlines() {
+        // Any whitespace is a boundary, and gets replaced, not only the spaces
+        let pretok = Metaspace::new('▁', true);
+        let mut input = NormalizedString::from("a\tb\nc");
+        let res = pretok.pre_tokenize(&mut input).unwrap();
+        assert_eq!(
+            &res,
+            &[
+                ("▁a".into(), (0, 2)),
+                ("▁b".into(), (2, 4)),
+                ("▁c".into(), (4, 6)),
+            ]
+        );
+
+        let pretok = Metaspace::new('▁', false);
+        let mut input = NormalizedString::from("a\t\tb\r\nc");
+        let res = pretok.pre_tokenize(&mut input).unwrap();
+        assert_eq!(
+            &res,
+            &[
+                ("a".into(), (0, 1)),
+                ("▁".into(), (1, 2)),
+                ("▁b".into(), (2, 4)),
+                ("▁".into(), (4, 5)),
+                ("▁c".into(), (5, 7)),
+            ]
+        );
+    }
+
     #[test]
     fn decode() {
         let decoder = Metaspace::new('▁', true);
@@ -118,4 +163,40 @@ mod tests {
             .unwrap();
         assert_eq!(&res, "Hey friend!")
     }
+
+    #[test]
+    fn decode_prefix_space_on_first_char_only() {
+        let decoder = Metaspace::new('▁', true);
+        let res = decoder
+            .decode(vec![
+                "".into(),
+                "▁Hey".into(),
+                "▁".into(),
+                "▁friend!".into(),
+            ])
+            .unwrap();
+        assert_eq!(&res, "Hey  friend!");
+
+        let decoder = Metaspace::new('▁', false);
+        let res = decoder
+            .decode(vec!["▁Hey".into(), "▁friend!".into()])
+            .unwrap();
+        assert_eq!(&res, " Hey friend!");
+    }
+
+    #[test]
+    fn decode_groups() {
+        let decoder = Metaspace::new('▁', true);
+        let res = decoder
+            .decode_groups(vec![
+                vec!["▁Hey".into(), "▁friend!".into()],
+                vec!["▁How".into(), "▁are".into(), "▁you?".into()],
+            ])
+            .unwrap();
+        assert_eq!(&res, "Hey friend! How are you?");
+
+        let res = decoder.decode_groups(vec![vec!["Hey".into()]]).unwrap();
+        assert_eq!(&res, "Hey");
+        assert_eq!(&decoder.decode_groups(vec![]).unwrap(), "");
+    }
 }
@@ -1,5 +1,7 @@
 pub mod bert;
 pub mod byte_level;
 pub mod delimiter;
+pub mod fixed;
+pub mod identity;
 pub mod metaspace;
 pub mod whitespace;
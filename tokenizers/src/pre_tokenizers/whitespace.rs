@@ -1,30 +1,69 @@
 use crate::tokenizer::{NormalizedString, Offsets, PreTokenizer, Result};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
 
 #[derive(Serialize, Deserialize)]
 pub struct Whitespace;
+
+/// Same as `Whitespace`, but only splits between grapheme clusters, which keeps together for
+/// example the emoji sequences using a zero-width joiner (ZWJ), like `👨‍👩‍👧`
+#[derive(Serialize, Deserialize)]
+pub struct WhitespaceGraphemes;
+
+/// Split `s` on whitespaces and punctuation, with offsets in chars
+fn split_words(s: &str) -> Vec<(String, Offsets)> {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(r"\w+|[^\w\s]+").unwrap();
+    }
+    // The matches give byte offsets, while we need char offsets
+    let (mut last_byte, mut last_char) = (0, 0);
+    RE.find_iter(s)
+        .map(|m| {
+            let start = last_char + s[last_byte..m.start()].chars().count();
+            let end = start + m.as_str().chars().count();
+            last_byte = m.end();
+            last_char = end;
+            (m.as_str().to_owned(), (start, end))
+        })
+        .collect()
+}
+
+/// Merge the adjacent words whose boundary falls inside a grapheme cluster of `s`
+fn merge_graphemes(s: &str, words: Vec<(String, Offsets)>) -> Vec<(String, Offsets)> {
+    // The char offsets of the boundaries between grapheme clusters
+    let mut boundaries = vec![0];
+    for grapheme in s.graphemes(true) {
+        boundaries.push(boundaries.last().unwrap() + grapheme.chars().count());
+    }
+
+    let mut merged: Vec<(String, Offsets)> = Vec::with_capacity(words.len());
+    for (word, offsets) in words {
+        match merged.last_mut() {
+            Some((last, last_offsets))
+                if last_offsets.1 == offsets.0 && boundaries.binary_search(&offsets.0).is_err() =>
+            {
+                last.push_str(&word);
+                last_offsets.1 = offsets.1;
+            }
+            _ => merged.push((word, offsets)),
+        }
+    }
+    merged
+}
+
 #[typetag::serde]
 impl PreTokenizer for Whitespace {
     fn pre_tokenize(&self, normalized: &mut NormalizedString) -> Result<Vec<(String, Offsets)>> {
-        lazy_static! {
-            static ref RE: Regex = Regex::new(r"\w+|[^\w\s]+").unwrap();
-        }
-        Ok(RE
-            .captures_iter(normalized.get())
-            .flat_map(|captures| {
-                captures
-                    .iter()
-                    .map(|m| {
-                        m.map(|capture| {
-                            let (start, end) = (capture.start(), capture.end());
-                            (normalized.get()[start..end].to_owned(), (start, end))
-                        })
-                        .unwrap_or_else(|| (String::from(""), (0, 0)))
-                    })
-                    .collect::<Vec<(String, Offsets)>>()
-            })
-            .collect())
+        Ok(split_words(normalized.get()))
+    }
+}
+
+#[typetag::serde]
+impl PreTokenizer for WhitespaceGraphemes {
+    fn pre_tokenize(&self, normalized: &mut NormalizedString) -> Result<Vec<(String, Offsets)>> {
+        let words = split_words(normalized.get());
+        Ok(merge_graphemes(normalized.get(), words))
     }
 }
 
@@ -83,6 +122,14 @@ mod tests {
                     ("?".into(), (17, 18)),
                 ],
             ),
+            (
+                "Ça va\u{301} ?",
+                vec![
+                    ("Ça".into(), (0, 2)),
+                    ("va\u{301}".into(), (3, 6)),
+                    ("?".into(), (7, 8)),
+                ],
+            ),
         ];
         let pretok = Whitespace;
         for (s, res) in tests {
@@ -91,6 +138,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn zwj_sequences() {
+        let input = "Hi \u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}!";
+
+        let pretok = WhitespaceGraphemes;
+        let res = pretok
+            .pre_tokenize(&mut NormalizedString::from(input))
+            .unwrap();
+        assert_eq!(
+            res,
+            vec![
+                ("Hi".into(), (0, 2)),
+                (
+                    "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}!".into(),
+                    (3, 9)
+                ),
+            ]
+        );
+
+        // The ZWJ is a word char, so each emoji ends up in its own word otherwise
+        let res = Whitespace
+            .pre_tokenize(&mut NormalizedString::from(input))
+            .unwrap();
+        assert_eq!(res.len(), 6);
+    }
+
     #[test]
     fn whitespace_split() {
         let tests = vec![
@@ -1,3 +1,4 @@
+use crate::processors::special_token;
 use crate::tokenizer::{Encoding, PostProcessor, Result};
 use serde::{Deserialize, Serialize};
 
@@ -11,6 +12,14 @@ impl BertProcessing {
     pub fn new(sep: (String, u32), cls: (String, u32)) -> Self {
         BertProcessing { sep, cls }
     }
+
+    pub fn get_sep(&self) -> &(String, u32) {
+        &self.sep
+    }
+
+    pub fn get_cls(&self) -> &(String, u32) {
+        &self.cls
+    }
 }
 
 #[typetag::serde]
@@ -25,7 +34,7 @@ impl PostProcessor for BertProcessing {
 
     fn process(
         &self,
-        mut encoding: Encoding,
+        encoding: Encoding,
         pair_encoding: Option<Encoding>,
         add_special_tokens: bool,
     ) -> Result<Encoding> {
@@ -33,108 +42,81 @@ impl PostProcessor for BertProcessing {
             return PostProcessor::default_process(encoding, pair_encoding, add_special_tokens);
         }
 
-        let ids = [&[self.cls.1], &encoding.get_ids()[..], &[self.sep.1]].concat();
-        let type_ids = [&[0], &encoding.get_type_ids()[..], &[0]].concat();
-        let tokens = [
-            &[self.cls.0.clone()],
-            &encoding.get_tokens()[..],
-            &[self.sep.0.clone()],
-        ]
-        .concat();
-        let words = [&[None], &encoding.get_words()[..], &[None]].concat();
-        let offsets = [&[(0, 0)], &encoding.get_offsets()[..], &[(0, 0)]].concat();
-        let special_tokens = [&[1u32], &vec![0; encoding.get_ids().len()][..], &[1]].concat();
-        let attention_mask = vec![1; ids.len()];
+        let mut new_encoding = special_token(&self.cls, 0);
+        new_encoding.merge_with(encoding, false);
+        new_encoding.merge_with(special_token(&self.sep, 0), false);
 
-        let mut new_encoding = Encoding::new(
-            ids,
-            type_ids,
-            tokens,
-            words,
-            offsets,
-            special_tokens,
-            attention_mask,
-            encoding
-                .take_overflowing()
-                .into_iter()
-                .map(|encoding| {
-                    let ids = [&[self.cls.1], &encoding.get_ids()[..], &[self.sep.1]].concat();
-                    let type_ids = [&[0], &encoding.get_type_ids()[..], &[0]].concat();
-                    let tokens = [
-                        &[self.cls.0.clone()],
-                        &encoding.get_tokens()[..],
-                        &[self.sep.0.clone()],
-                    ]
-                    .concat();
-                    let words = [&[None], &encoding.get_words()[..], &[None]].concat();
-                    let offsets = [&[(0, 0)], &encoding.get_offsets()[..], &[(0, 0)]].concat();
-                    let special_tokens =
-                        [&[1u32], &vec![0; encoding.get_ids().len()][..], &[1]].concat();
-                    let attention_mask = vec![1; ids.len()];
+        if let Some(mut pair_encoding) = pair_encoding {
+            pair_encoding.merge_with(special_token(&self.sep, 1), false);
+            new_encoding.merge_with(pair_encoding, false);
+        }
 
-                    Encoding::new(
-                        ids,
-                        type_ids,
-                        tokens,
-                        words,
-                        offsets,
-                        special_tokens,
-                        attention_mask,
-                        vec![],
-                    )
-                })
-                .collect(),
-        );
+        Ok(new_encoding)
+    }
+}
 
-        if let Some(mut encoding) = pair_encoding {
-            let pair_ids = [&encoding.get_ids()[..], &[self.sep.1]].concat();
-            let pair_type_ids = [&encoding.get_type_ids()[..], &[1]].concat();
-            let pair_tokens = [&encoding.get_tokens()[..], &[self.sep.0.clone()]].concat();
-            let pair_words = [&encoding.get_words()[..], &[None]].concat();
-            let pair_offsets = [&encoding.get_offsets()[..], &[(0, 0)]].concat();
-            let pair_special_tokens =
-                [&vec![0u32; encoding.get_type_ids().len()][..], &[1]].concat();
-            let pair_attention_mask = vec![1; pair_ids.len()];
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Token;
 
-            let new_pair_encoding = Encoding::new(
-                pair_ids,
-                pair_type_ids,
-                pair_tokens,
-                pair_words,
-                pair_offsets,
-                pair_special_tokens,
-                pair_attention_mask,
-                encoding
-                    .take_overflowing()
-                    .into_iter()
-                    .map(|encoding| {
-                        let pair_ids = [&encoding.get_ids()[..], &[self.sep.1]].concat();
-                        let pair_type_ids = [&encoding.get_type_ids()[..], &[1]].concat();
-                        let pair_tokens =
-                            [&encoding.get_tokens()[..], &[self.sep.0.clone()]].concat();
-                        let pair_words = [&encoding.get_words()[..], &[None]].concat();
-                        let pair_offsets = [&encoding.get_offsets()[..], &[(0, 0)]].concat();
-                        let pair_special_tokens =
-                            [&vec![0u32; encoding.get_type_ids().len()][..], &[1]].concat();
-                        let pair_attention_mask = vec![1; pair_ids.len()];
+    #[test]
+    fn getters() {
+        let processor = BertProcessing::new(("[SEP]".into(), 102), ("[CLS]".into(), 101));
+        assert_eq!(processor.get_sep(), &("[SEP]".into(), 102));
+        assert_eq!(processor.get_cls(), &("[CLS]".into(), 101));
+    }
 
-                        Encoding::new(
-                            pair_ids,
-                            pair_type_ids,
-                            pair_tokens,
-                            pair_words,
-                            pair_offsets,
-                            pair_special_tokens,
-                            pair_attention_mask,
-                            vec![],
-                        )
-                    })
-                    .collect(),
-            );
+    #[test]
+    fn process_pair() {
+        let processor = BertProcessing::new(("[SEP]".into(), 102), ("[CLS]".into(), 101));
+        let encoding = Encoding::from_tokens(
+            vec![
+                Token::new(12, "Hello".into(), (0, 5), 0),
+                Token::new(14, "there".into(), (6, 11), 1),
+            ],
+            0,
+        );
+        let pair = Encoding::from_tokens(vec![Token::new(15, "pair".into(), (0, 4), 0)], 1);
 
-            new_encoding.merge_with(new_pair_encoding, false);
-        }
+        let encoding = processor.process(encoding, Some(pair), true).unwrap();
+        assert_eq!(
+            encoding.get_tokens(),
+            &["[CLS]", "Hello", "there", "[SEP]", "pair", "[SEP]"]
+        );
+        assert_eq!(encoding.get_ids(), &[101, 12, 14, 102, 15, 102]);
+        assert_eq!(encoding.get_type_ids(), &[0, 0, 0, 0, 1, 1]);
+        assert_eq!(encoding.get_special_tokens_mask(), &[1, 0, 0, 1, 0, 1]);
+        assert_eq!(encoding.get_attention_mask(), &[1, 1, 1, 1, 1, 1]);
+        assert_eq!(
+            encoding.get_offsets(),
+            &[(0, 0), (0, 5), (6, 11), (0, 0), (0, 4), (0, 0)]
+        );
+        assert_eq!(
+            encoding.get_words(),
+            &[None, Some(0), Some(1), None, Some(2), None]
+        );
+    }
 
-        Ok(new_encoding)
+    #[test]
+    fn process_overflowing_and_unk_mask() {
+        let processor = BertProcessing::new(("[SEP]".into(), 102), ("[CLS]".into(), 101));
+        let mut encoding = Encoding::from_tokens(
+            vec![
+                Token::new(12, "Hello".into(), (0, 5), 0),
+                Token::new(0, "[UNK]".into(), (6, 11), 1),
+            ],
+            0,
+        );
+        encoding.set_unk_mask(vec![false, true]);
+        encoding.truncate(1, 0);
+
+        let encoding = processor.process(encoding, None, true).unwrap();
+        assert_eq!(encoding.get_tokens(), &["[CLS]", "Hello", "[SEP]"]);
+        assert_eq!(encoding.get_unk_mask(), &[false, false, false]);
+        let overflowing = &encoding.get_overflowing()[0];
+        assert_eq!(overflowing.get_tokens(), &["[CLS]", "[UNK]", "[SEP]"]);
+        assert_eq!(overflowing.get_unk_mask(), &[false, true, false]);
+        assert_eq!(overflowing.get_special_tokens_mask(), &[1, 0, 1]);
     }
 }
@@ -0,0 +1,136 @@
+use crate::processors::special_token;
+use crate::tokenizer::{Encoding, PostProcessor, Result};
+use serde::{Deserialize, Serialize};
+
+/// Prepends a BOS token and appends an EOS token, like for causal language models. A pair
+/// gets `BOS A EOS B EOS`, where the tokens of `B`, including its EOS, have the type id 1.
+/// Both tokens are optional.
+#[derive(Serialize, Deserialize)]
+pub struct BosEos {
+    bos: Option<(String, u32)>,
+    eos: Option<(String, u32)>,
+}
+
+impl BosEos {
+    /// Create a new `BosEos` with the given BOS and EOS tokens, each with its id. Use `None`
+    /// to skip either of them.
+    pub fn new(bos: Option<(String, u32)>, eos: Option<(String, u32)>) -> Self {
+        BosEos { bos, eos }
+    }
+}
+
+#[typetag::serde]
+impl PostProcessor for BosEos {
+    fn added_tokens(&self, is_pair: bool) -> usize {
+        let eos = if is_pair { 2 } else { 1 };
+        usize::from(self.bos.is_some()) + usize::from(self.eos.is_some()) * eos
+    }
+
+    fn process(
+        &self,
+        mut encoding: Encoding,
+        pair_encoding: Option<Encoding>,
+        add_special_tokens: bool,
+    ) -> Result<Encoding> {
+        if !add_special_tokens {
+            return PostProcessor::default_process(encoding, pair_encoding, add_special_tokens);
+        }
+
+        if let Some(bos) = &self.bos {
+            let mut new_encoding = special_token(bos, 0);
+            new_encoding.merge_with(encoding, false);
+            encoding = new_encoding;
+        }
+        if let Some(eos) = &self.eos {
+            encoding.merge_with(special_token(eos, 0), false);
+        }
+
+        if let Some(mut pair) = pair_encoding {
+            pair.set_type_ids(1);
+            if let Some(eos) = &self.eos {
+                pair.merge_with(special_token(eos, 1), false);
+            }
+            encoding.merge_with(pair, false);
+        }
+
+        Ok(encoding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Token;
+
+    fn get_pair() -> (Encoding, Encoding) {
+        let encoding = Encoding::from_tokens(
+            vec![
+                Token::new(12, "Hello".into(), (0, 5), 0),
+                Token::new(14, "there".into(), (6, 11), 1),
+            ],
+            0,
+        );
+        let pair = Encoding::from_tokens(vec![Token::new(15, "pair".into(), (0, 4), 0)], 1);
+        (encoding, pair)
+    }
+
+    #[test]
+    fn process_single() {
+        let processor = BosEos::new(Some(("<s>".into(), 1)), Some(("</s>".into(), 2)));
+        assert_eq!(processor.added_tokens(false), 2);
+
+        let (encoding, _) = get_pair();
+        let encoding = processor.process(encoding, None, true).unwrap();
+        assert_eq!(encoding.get_tokens(), &["<s>", "Hello", "there", "</s>"]);
+        assert_eq!(encoding.get_ids(), &[1, 12, 14, 2]);
+        assert_eq!(encoding.get_type_ids(), &[0, 0, 0, 0]);
+        assert_eq!(encoding.get_special_tokens_mask(), &[1, 0, 0, 1]);
+        assert_eq!(encoding.get_attention_mask(), &[1, 1, 1, 1]);
+        assert_eq!(encoding.get_words(), &[None, Some(0), Some(1), None]);
+        assert_eq!(encoding.get_offsets(), &[(0, 0), (0, 5), (6, 11), (0, 0)]);
+
+        let (encoding, _) = get_pair();
+        let encoding = processor.process(encoding, None, false).unwrap();
+        assert_eq!(encoding.get_ids(), &[12, 14]);
+    }
+
+    #[test]
+    fn process_pair() {
+        let processor = BosEos::new(Some(("<s>".into(), 1)), Some(("</s>".into(), 2)));
+        assert_eq!(processor.added_tokens(true), 3);
+
+        let (encoding, pair) = get_pair();
+        let encoding = processor.process(encoding, Some(pair), true).unwrap();
+        assert_eq!(
+            encoding.get_tokens(),
+            &["<s>", "Hello", "there", "</s>", "pair", "</s>"]
+        );
+        assert_eq!(encoding.get_ids(), &[1, 12, 14, 2, 15, 2]);
+        assert_eq!(encoding.get_type_ids(), &[0, 0, 0, 0, 1, 1]);
+        assert_eq!(encoding.get_special_tokens_mask(), &[1, 0, 0, 1, 0, 1]);
+        assert_eq!(
+            encoding.get_offsets(),
+            &[(0, 0), (0, 5), (6, 11), (0, 0), (0, 4), (0, 0)]
+        );
+    }
+
+    #[test]
+    fn eos_only() {
+        let processor: Box<dyn PostProcessor> =
+            Box::new(BosEos::new(None, Some(("</s>".into(), 2))));
+        assert_eq!(processor.added_tokens(false), 1);
+        assert_eq!(processor.added_tokens(true), 2);
+
+        let (encoding, pair) = get_pair();
+        let encoding = processor.process(encoding, Some(pair), true).unwrap();
+        assert_eq!(encoding.get_ids(), &[12, 14, 2, 15, 2]);
+
+        let serialized = serde_json::to_string(&processor).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"BosEos","bos":null,"eos":["</s>",2]}"#
+        );
+        let processor: Box<dyn PostProcessor> = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(processor.added_tokens(true), 2);
+    }
+}
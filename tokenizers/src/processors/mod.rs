@@ -1,5 +1,24 @@
 pub mod bert;
+pub mod bos_eos;
+pub mod pair_padding;
 pub mod roberta;
+pub mod template;
 
 // Re-export these as processors
 pub use super::pre_tokenizers::byte_level;
+
+use crate::tokenizer::Encoding;
+
+/// An `Encoding` with the given special token only, to be merged with the processed sequences
+pub(crate) fn special_token((token, id): &(String, u32), type_id: u32) -> Encoding {
+    Encoding::new(
+        vec![*id],
+        vec![type_id],
+        vec![token.clone()],
+        vec![None],
+        vec![(0, 0)],
+        vec![1],
+        vec![1],
+        vec![],
+    )
+}
@@ -0,0 +1,152 @@
+use crate::tokenizer::{Encoding, PaddingDirection, PostProcessor, Result};
+use serde::{Deserialize, Serialize};
+
+/// Pads the first and the second sequence of a pair each to its own fixed length before
+/// merging them, so that a pair always gets the layout `a_length + b_length`, like some
+/// siamese or cross-encoder models expect.
+///
+/// The pads of each sequence take the `type_id` of the sequence they follow (the one of its
+/// first token, or `0` for the first sequence and `1` for the second one when empty), have the
+/// offsets `(0, 0)`, and are masked in both the `attention_mask` and the `special_tokens_mask`.
+/// A sequence already longer than its target is left as is: use truncation to bound it.
+///
+/// The padded sequences are then simply concatenated, without any special token: processors
+/// like `BertProcessing` mark all the tokens of a sequence as attended, which would undo the
+/// masks of the pads.
+#[derive(Serialize, Deserialize)]
+pub struct PairPaddingProcessing {
+    a_length: usize,
+    b_length: usize,
+    pad_id: u32,
+    pad_token: String,
+    direction: PaddingDirection,
+}
+
+impl PairPaddingProcessing {
+    pub fn new(a_length: usize, b_length: usize) -> Self {
+        PairPaddingProcessing {
+            a_length,
+            b_length,
+            pad_id: 0,
+            pad_token: String::from("[PAD]"),
+            direction: PaddingDirection::Right,
+        }
+    }
+    pub fn pad_id(mut self, pad_id: u32) -> Self {
+        self.pad_id = pad_id;
+        self
+    }
+    pub fn pad_token(mut self, pad_token: String) -> Self {
+        self.pad_token = pad_token;
+        self
+    }
+    pub fn direction(mut self, direction: PaddingDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    fn pad(&self, encoding: &mut Encoding, target_length: usize, default_type_id: u32) {
+        let type_id = encoding
+            .get_type_ids()
+            .first()
+            .copied()
+            .unwrap_or(default_type_id);
+        encoding.pad(
+            target_length,
+            self.pad_id,
+            type_id,
+            &self.pad_token,
+            self.direction,
+        );
+    }
+}
+
+#[typetag::serde]
+impl PostProcessor for PairPaddingProcessing {
+    fn added_tokens(&self, _is_pair: bool) -> usize {
+        // The pads are not counted: they only fill the space left after truncation
+        0
+    }
+
+    fn process(
+        &self,
+        mut encoding: Encoding,
+        mut pair_encoding: Option<Encoding>,
+        add_special_tokens: bool,
+    ) -> Result<Encoding> {
+        self.pad(&mut encoding, self.a_length, 0);
+        if let Some(pair) = pair_encoding.as_mut() {
+            self.pad(pair, self.b_length, 1);
+        }
+
+        PostProcessor::default_process(encoding, pair_encoding, add_special_tokens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Token;
+
+    fn get_pair() -> (Encoding, Encoding) {
+        let encoding = Encoding::from_tokens(
+            vec![
+                Token::new(12, "Hello".into(), (0, 5), 0),
+                Token::new(14, "there".into(), (6, 11), 1),
+            ],
+            0,
+        );
+        let pair = Encoding::from_tokens(vec![Token::new(15, "pair".into(), (0, 4), 0)], 1);
+        (encoding, pair)
+    }
+
+    #[test]
+    fn fixed_lengths() {
+        let processor = PairPaddingProcessing::new(4, 3);
+        let (encoding, pair) = get_pair();
+        let encoding = processor.process(encoding, Some(pair), true).unwrap();
+        assert_eq!(
+            encoding.get_tokens(),
+            &["Hello", "there", "[PAD]", "[PAD]", "pair", "[PAD]", "[PAD]"]
+        );
+        assert_eq!(encoding.get_ids(), &[12, 14, 0, 0, 15, 0, 0]);
+        assert_eq!(encoding.get_type_ids(), &[0, 0, 0, 0, 1, 1, 1]);
+        assert_eq!(encoding.get_attention_mask(), &[1, 1, 0, 0, 1, 0, 0]);
+        assert_eq!(encoding.get_special_tokens_mask(), &[0, 0, 1, 1, 0, 1, 1]);
+        assert_eq!(
+            encoding.get_offsets(),
+            &[(0, 5), (6, 11), (0, 0), (0, 0), (0, 4), (0, 0), (0, 0)]
+        );
+
+        // Only the first sequence gets padded without a pair
+        let (encoding, _) = get_pair();
+        let encoding = processor.process(encoding, None, true).unwrap();
+        assert_eq!(encoding.get_ids(), &[12, 14, 0, 0]);
+    }
+
+    #[test]
+    fn left_padding() {
+        let processor = PairPaddingProcessing::new(3, 2)
+            .pad_id(1)
+            .pad_token("<pad>".into())
+            .direction(PaddingDirection::Left);
+        let (encoding, pair) = get_pair();
+        let encoding = processor.process(encoding, Some(pair), true).unwrap();
+        assert_eq!(
+            encoding.get_tokens(),
+            &["<pad>", "Hello", "there", "<pad>", "pair"]
+        );
+        assert_eq!(encoding.get_ids(), &[1, 12, 14, 1, 15]);
+        assert_eq!(encoding.get_type_ids(), &[0, 0, 0, 1, 1]);
+        assert_eq!(
+            encoding.get_words(),
+            &[None, Some(0), Some(1), None, Some(0)]
+        );
+
+        let serialized = serde_json::to_string(&processor).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"a_length":3,"b_length":2,"pad_id":1,"pad_token":"<pad>","direction":"Left"}"#
+        );
+    }
+}
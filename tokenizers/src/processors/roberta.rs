@@ -1,4 +1,4 @@
-use crate::processors::byte_level::process_offsets;
+use crate::processors::{byte_level::process_offsets, special_token};
 use crate::tokenizer::{Encoding, PostProcessor, Result};
 use serde::{Deserialize, Serialize};
 
@@ -75,119 +75,86 @@ impl PostProcessor for RobertaProcessing {
             return PostProcessor::default_process(encoding, pair_encoding, add_special_tokens);
         }
 
-        let ids = [&[self.cls.1], &encoding.get_ids()[..], &[self.sep.1]].concat();
-        let type_ids = [&[0], &encoding.get_type_ids()[..], &[0]].concat();
-        let tokens = [
-            &[self.cls.0.clone()],
-            &encoding.get_tokens()[..],
-            &[self.sep.0.clone()],
-        ]
-        .concat();
-        let words = [&[None], &encoding.get_words()[..], &[None]].concat();
-        let offsets = [&[(0, 0)], &encoding.get_offsets()[..], &[(0, 0)]].concat();
-        let special_tokens = [&[1u32], &vec![0; encoding.get_ids().len()][..], &[1]].concat();
-        let attention_mask = vec![1; ids.len()];
-
-        let mut new_encoding = Encoding::new(
-            ids,
-            type_ids,
-            tokens,
-            words,
-            offsets,
-            special_tokens,
-            attention_mask,
-            encoding
-                .take_overflowing()
-                .into_iter()
-                .map(|encoding| {
-                    let ids = [&[self.cls.1], &encoding.get_ids()[..], &[self.sep.1]].concat();
-                    let type_ids = [&[0], &encoding.get_type_ids()[..], &[0]].concat();
-                    let tokens = [
-                        &[self.cls.0.clone()],
-                        &encoding.get_tokens()[..],
-                        &[self.sep.0.clone()],
-                    ]
-                    .concat();
-                    let words = [&[None], &encoding.get_words()[..], &[None]].concat();
-                    let offsets = [&[(0, 0)], &encoding.get_offsets()[..], &[(0, 0)]].concat();
-                    let special_tokens =
-                        [&[1u32], &vec![0; encoding.get_ids().len()][..], &[1]].concat();
-                    let attention_mask = vec![1; ids.len()];
-
-                    Encoding::new(
-                        ids,
-                        type_ids,
-                        tokens,
-                        words,
-                        offsets,
-                        special_tokens,
-                        attention_mask,
-                        vec![],
-                    )
-                })
-                .collect(),
-        );
-
-        if let Some(mut encoding) = pair_encoding {
-            let pair_ids = [&[self.sep.1], &encoding.get_ids()[..], &[self.sep.1]].concat();
-            let pair_type_ids = vec![0; encoding.get_ids().len() + 2];
-            let pair_tokens = [
-                &[self.sep.0.clone()],
-                &encoding.get_tokens()[..],
-                &[self.sep.0.clone()],
-            ]
-            .concat();
-            let pair_words = [&[None], &encoding.get_words()[..], &[None]].concat();
-            let pair_offsets = [&[(0, 0)], &encoding.get_offsets()[..], &[(0, 0)]].concat();
-            let pair_special_tokens =
-                [&[1], &vec![0u32; encoding.get_type_ids().len()][..], &[1]].concat();
-            let pair_attention_mask = vec![1; pair_ids.len()];
-
-            let new_pair_encoding = Encoding::new(
-                pair_ids,
-                pair_type_ids,
-                pair_tokens,
-                pair_words,
-                pair_offsets,
-                pair_special_tokens,
-                pair_attention_mask,
-                encoding
-                    .take_overflowing()
-                    .into_iter()
-                    .map(|encoding| {
-                        let pair_ids =
-                            [&[self.sep.1], &encoding.get_ids()[..], &[self.sep.1]].concat();
-                        let pair_type_ids = vec![0; encoding.get_ids().len() + 2];
-                        let pair_tokens = [
-                            &[self.sep.0.clone()],
-                            &encoding.get_tokens()[..],
-                            &[self.sep.0.clone()],
-                        ]
-                        .concat();
-                        let pair_words = [&[None], &encoding.get_words()[..], &[None]].concat();
-                        let pair_offsets =
-                            [&[(0, 0)], &encoding.get_offsets()[..], &[(0, 0)]].concat();
-                        let pair_special_tokens =
-                            [&[1], &vec![0u32; encoding.get_type_ids().len()][..], &[1]].concat();
-                        let pair_attention_mask = vec![1; pair_ids.len()];
-
-                        Encoding::new(
-                            pair_ids,
-                            pair_type_ids,
-                            pair_tokens,
-                            pair_words,
-                            pair_offsets,
-                            pair_special_tokens,
-                            pair_attention_mask,
-                            vec![],
-                        )
-                    })
-                    .collect(),
-            );
+        let mut new_encoding = special_token(&self.cls, 0);
+        new_encoding.merge_with(encoding, false);
+        new_encoding.merge_with(special_token(&self.sep, 0), false);
 
+        if let Some(mut pair_encoding) = pair_encoding {
+            // Roberta doesn't use the type ids, so the pair has the same ones
+            pair_encoding.set_type_ids(0);
+            let mut new_pair_encoding = special_token(&self.sep, 0);
+            new_pair_encoding.merge_with(pair_encoding, false);
+            new_pair_encoding.merge_with(special_token(&self.sep, 0), false);
             new_encoding.merge_with(new_pair_encoding, false);
         }
 
         Ok(new_encoding)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Token;
+
+    fn get_pair() -> (Encoding, Encoding) {
+        let encoding = Encoding::from_tokens(
+            vec![
+                Token::new(12, "Hello".into(), (0, 5), 0),
+                Token::new(14, "Ġthere".into(), (5, 11), 1),
+            ],
+            0,
+        );
+        let pair = Encoding::from_tokens(vec![Token::new(15, "Ġpair".into(), (0, 5), 0)], 1);
+        (encoding, pair)
+    }
+
+    #[test]
+    fn process_single() {
+        let processor = RobertaProcessing::default();
+        assert_eq!(processor.added_tokens(false), 2);
+
+        let (encoding, _) = get_pair();
+        let encoding = processor.process(encoding, None, true).unwrap();
+        assert_eq!(encoding.get_tokens(), &["<s>", "Hello", "Ġthere", "</s>"]);
+        assert_eq!(encoding.get_ids(), &[0, 12, 14, 2]);
+        assert_eq!(encoding.get_special_tokens_mask(), &[1, 0, 0, 1]);
+        // The space before `there` is trimmed
+        assert_eq!(encoding.get_offsets(), &[(0, 0), (0, 5), (6, 11), (0, 0)]);
+    }
+
+    #[test]
+    fn process_pair() {
+        let processor = RobertaProcessing::default().trim_offsets(false);
+        assert_eq!(processor.added_tokens(true), 4);
+
+        let (encoding, pair) = get_pair();
+        let encoding = processor.process(encoding, Some(pair), true).unwrap();
+        assert_eq!(
+            encoding.get_tokens(),
+            &["<s>", "Hello", "Ġthere", "</s>", "</s>", "Ġpair", "</s>"]
+        );
+        assert_eq!(encoding.get_ids(), &[0, 12, 14, 2, 2, 15, 2]);
+        assert_eq!(encoding.get_type_ids(), &[0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(encoding.get_special_tokens_mask(), &[1, 0, 0, 1, 1, 0, 1]);
+        assert_eq!(
+            encoding.get_offsets(),
+            &[(0, 0), (0, 5), (5, 11), (0, 0), (0, 0), (0, 5), (0, 0)]
+        );
+    }
+
+    #[test]
+    fn serialization() {
+        let processor: Box<dyn PostProcessor> = Box::new(
+            RobertaProcessing::new(("[SEP]".into(), 102), ("[CLS]".into(), 101))
+                .add_prefix_space(false),
+        );
+        let serialized = serde_json::to_string(&processor).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"RobertaProcessing","sep":["[SEP]",102],"cls":["[CLS]",101],"trim_offsets":true,"add_prefix_space":false}"#
+        );
+        let processor: Box<dyn PostProcessor> = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(processor.added_tokens(true), 4);
+    }
+}
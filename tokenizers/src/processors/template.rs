@@ -0,0 +1,519 @@
+//! A `PostProcessor` adding the special tokens following some templates, like
+//! `[CLS] $A [SEP]` for a single sequence, or `[CLS] $A [SEP] $B:1 [SEP]:1` for a pair.
+//!
+//! Each piece of a template is separated by whitespace, and is either one of the sequences
+//! (`$A` or `$B`) or a special token. Any piece can be followed by `:<type_id>` to specify its
+//! type id, which otherwise defaults to 0.
+use crate::tokenizer::{Encoding, PostProcessor, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// Errors that can be encountered while building a `TemplateProcessing`
+#[derive(Debug)]
+pub enum BuilderError {
+    /// No template was provided for the single sequences
+    MissingSingleTemplate,
+    /// A piece of a template couldn't be parsed
+    InvalidPiece(String),
+    /// A special token used in a template has no id
+    MissingSpecialToken(String),
+    /// A sequence doesn't appear the right number of times in a template
+    SequenceCount {
+        template: String,
+        sequence: Sequence,
+        expected: usize,
+        found: usize,
+    },
+    /// A pair of sequences is being processed, but no pair template was provided
+    MissingPairTemplate,
+}
+
+impl std::fmt::Display for BuilderError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            BuilderError::MissingSingleTemplate => write!(
+                fmt,
+                "Template error: Missing the template for single sequences"
+            ),
+            BuilderError::InvalidPiece(piece) => {
+                write!(fmt, "Template error: Invalid piece {:?}", piece)
+            }
+            BuilderError::MissingSpecialToken(token) => write!(
+                fmt,
+                "Template error: Missing the id of the special token {:?}",
+                token
+            ),
+            BuilderError::SequenceCount {
+                template,
+                sequence,
+                expected,
+                found,
+            } => write!(
+                fmt,
+                "Template error: Expected {} {:?} in {:?}, found {}",
+                expected, sequence, template, found
+            ),
+            BuilderError::MissingPairTemplate => write!(
+                fmt,
+                "Template error: Missing the template for pairs of sequences"
+            ),
+        }
+    }
+}
+impl std::error::Error for BuilderError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Sequence {
+    A,
+    B,
+}
+
+/// A piece of a template
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Piece {
+    Sequence { id: Sequence, type_id: u32 },
+    SpecialToken { id: String, type_id: u32 },
+}
+
+impl TryFrom<&str> for Piece {
+    type Error = BuilderError;
+
+    fn try_from(piece: &str) -> std::result::Result<Self, Self::Error> {
+        let (name, type_id) = match piece.rfind(':') {
+            Some(i) => match piece[i + 1..].parse::<u32>() {
+                Ok(type_id) => (&piece[..i], type_id),
+                Err(_) => (piece, 0),
+            },
+            None => (piece, 0),
+        };
+
+        match name {
+            "" => Err(BuilderError::InvalidPiece(piece.to_owned())),
+            "$A" => Ok(Piece::Sequence {
+                id: Sequence::A,
+                type_id,
+            }),
+            "$B" => Ok(Piece::Sequence {
+                id: Sequence::B,
+                type_id,
+            }),
+            n if n.starts_with('$') => Err(BuilderError::InvalidPiece(piece.to_owned())),
+            n => Ok(Piece::SpecialToken {
+                id: n.to_owned(),
+                type_id,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Piece {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Piece::Sequence { id, type_id } => write!(fmt, "${:?}:{}", id, type_id),
+            Piece::SpecialToken { id, type_id } => write!(fmt, "{}:{}", id, type_id),
+        }
+    }
+}
+
+/// A template, as a list of pieces
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Template(Vec<Piece>);
+
+impl TryFrom<&str> for Template {
+    type Error = BuilderError;
+
+    fn try_from(template: &str) -> std::result::Result<Self, Self::Error> {
+        Ok(Template(
+            template
+                .split_whitespace()
+                .map(Piece::try_from)
+                .collect::<std::result::Result<_, _>>()?,
+        ))
+    }
+}
+
+/// Formats the template with the type id of every piece, so that it parses back to the same
+/// template.
+impl fmt::Display for Template {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let pieces = self.0.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        write!(fmt, "{}", pieces.join(" "))
+    }
+}
+
+impl Template {
+    pub fn get_pieces(&self) -> &[Piece] {
+        &self.0
+    }
+
+    fn count_sequence(&self, sequence: Sequence) -> usize {
+        self.0
+            .iter()
+            .filter(|p| matches!(p, Piece::Sequence { id, .. } if *id == sequence))
+            .count()
+    }
+
+    fn count_special_tokens(&self) -> usize {
+        self.0
+            .iter()
+            .filter(|p| matches!(p, Piece::SpecialToken { .. }))
+            .count()
+    }
+}
+
+/// A `TemplateProcessingBuilder` can be used to create a `TemplateProcessing`, validating
+/// its templates.
+#[derive(Default)]
+pub struct TemplateProcessingBuilder {
+    single: Option<String>,
+    pair: Option<String>,
+    special_tokens: HashMap<String, u32>,
+}
+
+impl TemplateProcessingBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the template for single sequences. It must contain `$A` once, and no `$B`.
+    pub fn single(mut self, template: String) -> Self {
+        self.single = Some(template);
+        self
+    }
+
+    /// Set the template for pairs of sequences. It must contain both `$A` and `$B` once.
+    /// Processing a pair without this template fails.
+    pub fn pair(mut self, template: String) -> Self {
+        self.pair = Some(template);
+        self
+    }
+
+    /// Set the ids of the special tokens used in the templates.
+    pub fn special_tokens(mut self, special_tokens: Vec<(String, u32)>) -> Self {
+        self.special_tokens.extend(special_tokens);
+        self
+    }
+
+    /// Returns a `TemplateProcessing` after validating the templates.
+    pub fn build(self) -> Result<TemplateProcessing> {
+        let single = self
+            .single
+            .as_ref()
+            .ok_or(BuilderError::MissingSingleTemplate)?;
+        let single = Template::try_from(single.as_str())?;
+        self.validate(&single, 0)?;
+        let pair = match &self.pair {
+            Some(pair) => {
+                let pair = Template::try_from(pair.as_str())?;
+                self.validate(&pair, 1)?;
+                Some(pair)
+            }
+            None => None,
+        };
+
+        Ok(TemplateProcessing {
+            single,
+            pair,
+            special_tokens: self.special_tokens,
+        })
+    }
+
+    /// Validate the given template, which must contain `$B` exactly `expected_b` times, and
+    /// only special tokens with an id.
+    fn validate(&self, parsed: &Template, expected_b: usize) -> Result<()> {
+        for (sequence, expected) in &[(Sequence::A, 1), (Sequence::B, expected_b)] {
+            let found = parsed.count_sequence(*sequence);
+            if found != *expected {
+                return Err(Box::new(BuilderError::SequenceCount {
+                    template: parsed.to_string(),
+                    sequence: *sequence,
+                    expected: *expected,
+                    found,
+                }));
+            }
+        }
+
+        if let Some(token) = parsed.0.iter().find_map(|p| match p {
+            Piece::SpecialToken { id, .. } if !self.special_tokens.contains_key(id) => Some(id),
+            _ => None,
+        }) {
+            return Err(Box::new(BuilderError::MissingSpecialToken(token.clone())));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+pub struct TemplateProcessing {
+    single: Template,
+    pair: Option<Template>,
+    special_tokens: HashMap<String, u32>,
+}
+
+impl TemplateProcessing {
+    pub fn builder() -> TemplateProcessingBuilder {
+        TemplateProcessingBuilder::new()
+    }
+
+    pub fn get_single(&self) -> &Template {
+        &self.single
+    }
+
+    pub fn get_pair(&self) -> Option<&Template> {
+        self.pair.as_ref()
+    }
+}
+
+/// The templates get validated just like with the builder
+impl<'de> Deserialize<'de> for TemplateProcessing {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Unvalidated {
+            single: Template,
+            pair: Option<Template>,
+            special_tokens: HashMap<String, u32>,
+        }
+
+        let Unvalidated {
+            single,
+            pair,
+            special_tokens,
+        } = Unvalidated::deserialize(deserializer)?;
+        let builder = TemplateProcessingBuilder {
+            special_tokens,
+            ..Default::default()
+        };
+        builder
+            .validate(&single, 0)
+            .map_err(serde::de::Error::custom)?;
+        if let Some(pair) = &pair {
+            builder
+                .validate(pair, 1)
+                .map_err(serde::de::Error::custom)?;
+        }
+
+        Ok(TemplateProcessing {
+            single,
+            pair,
+            special_tokens: builder.special_tokens,
+        })
+    }
+}
+
+#[typetag::serde]
+impl PostProcessor for TemplateProcessing {
+    fn added_tokens(&self, is_pair: bool) -> usize {
+        if is_pair {
+            self.pair.as_ref().map_or(0, |t| t.count_special_tokens())
+        } else {
+            self.single.count_special_tokens()
+        }
+    }
+
+    fn process(
+        &self,
+        encoding: Encoding,
+        pair_encoding: Option<Encoding>,
+        add_special_tokens: bool,
+    ) -> Result<Encoding> {
+        if !add_special_tokens {
+            return PostProcessor::default_process(encoding, pair_encoding, add_special_tokens);
+        }
+
+        let template = if pair_encoding.is_some() {
+            self.pair
+                .as_ref()
+                .ok_or(BuilderError::MissingPairTemplate)?
+        } else {
+            &self.single
+        };
+
+        let mut sequences = vec![Some(encoding), pair_encoding];
+        let mut new_encoding = Encoding::default();
+        for piece in &template.0 {
+            let encoding = match piece {
+                Piece::Sequence { id, type_id } => {
+                    // Each sequence appears once in a validated template
+                    let index = match id {
+                        Sequence::A => 0,
+                        Sequence::B => 1,
+                    };
+                    let mut encoding = sequences[index].take().unwrap_or_default();
+                    encoding.set_type_ids(*type_id);
+                    encoding
+                }
+                Piece::SpecialToken { id, type_id } => Encoding::new(
+                    vec![*self
+                        .special_tokens
+                        .get(id)
+                        .ok_or_else(|| BuilderError::MissingSpecialToken(id.clone()))?],
+                    vec![*type_id],
+                    vec![id.clone()],
+                    vec![None],
+                    vec![(0, 0)],
+                    vec![1],
+                    vec![1],
+                    vec![],
+                ),
+            };
+            new_encoding.merge_with(encoding, false);
+        }
+
+        Ok(new_encoding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Token;
+
+    fn bert_builder() -> TemplateProcessingBuilder {
+        TemplateProcessing::builder()
+            .single("[CLS] $A [SEP]".into())
+            .pair("[CLS] $A [SEP] $B:1 [SEP]:1".into())
+            .special_tokens(vec![("[CLS]".into(), 101), ("[SEP]".into(), 102)])
+    }
+
+    #[test]
+    fn parse_pieces() {
+        let template = Template::try_from("[CLS] $A [SEP]:1 $B:1 a:b").unwrap();
+        assert_eq!(
+            template.get_pieces(),
+            &[
+                Piece::SpecialToken {
+                    id: "[CLS]".into(),
+                    type_id: 0
+                },
+                Piece::Sequence {
+                    id: Sequence::A,
+                    type_id: 0
+                },
+                Piece::SpecialToken {
+                    id: "[SEP]".into(),
+                    type_id: 1
+                },
+                Piece::Sequence {
+                    id: Sequence::B,
+                    type_id: 1
+                },
+                Piece::SpecialToken {
+                    id: "a:b".into(),
+                    type_id: 0
+                },
+            ]
+        );
+        assert!(Template::try_from("$C").is_err());
+        assert!(Template::try_from(":1").is_err());
+    }
+
+    #[test]
+    fn missing_special_token_id() {
+        let err = bert_builder()
+            .pair("[CLS] $A [SEP] $B:1 [EOS]:1".into())
+            .build()
+            .err()
+            .unwrap();
+        match err.downcast_ref::<BuilderError>() {
+            Some(BuilderError::MissingSpecialToken(token)) => assert_eq!(token, "[EOS]"),
+            _ => panic!("Unexpected error: {}", err),
+        }
+    }
+
+    #[test]
+    fn single_referencing_pair() {
+        let err = bert_builder()
+            .single("[CLS] $A [SEP] $B".into())
+            .build()
+            .err()
+            .unwrap();
+        match err.downcast_ref::<BuilderError>() {
+            Some(BuilderError::SequenceCount {
+                sequence: Sequence::B,
+                expected: 0,
+                found: 1,
+                ..
+            }) => {}
+            _ => panic!("Unexpected error: {}", err),
+        }
+
+        assert!(bert_builder().pair("[CLS] $A $A".into()).build().is_err());
+        assert!(TemplateProcessing::builder().build().is_err());
+    }
+
+    #[test]
+    fn process_pair() {
+        let processor = bert_builder().build().unwrap();
+        assert_eq!(processor.added_tokens(false), 2);
+        assert_eq!(processor.added_tokens(true), 3);
+
+        let encoding = Encoding::from_tokens(
+            vec![
+                Token::new(12, "Hello".into(), (0, 5), 0),
+                Token::new(14, "there".into(), (6, 11), 1),
+            ],
+            0,
+        );
+        let pair = Encoding::from_tokens(vec![Token::new(15, "pair".into(), (0, 4), 0)], 0);
+
+        let encoding = processor.process(encoding, Some(pair), true).unwrap();
+        assert_eq!(
+            encoding.get_tokens(),
+            &["[CLS]", "Hello", "there", "[SEP]", "pair", "[SEP]"]
+        );
+        assert_eq!(encoding.get_ids(), &[101, 12, 14, 102, 15, 102]);
+        assert_eq!(encoding.get_type_ids(), &[0, 0, 0, 0, 1, 1]);
+        assert_eq!(encoding.get_special_tokens_mask(), &[1, 0, 0, 1, 0, 1]);
+        assert_eq!(
+            encoding.get_offsets(),
+            &[(0, 0), (0, 5), (6, 11), (0, 0), (0, 4), (0, 0)]
+        );
+    }
+
+    #[test]
+    fn process_pair_without_template() {
+        let processor = TemplateProcessing::builder()
+            .single("$A [SEP]".into())
+            .special_tokens(vec![("[SEP]".into(), 102)])
+            .build()
+            .unwrap();
+        let encoding = Encoding::from_tokens(vec![Token::new(12, "Hello".into(), (0, 5), 0)], 0);
+        let pair = encoding.clone();
+
+        assert_eq!(
+            processor
+                .process(encoding.clone(), None, true)
+                .unwrap()
+                .get_ids(),
+            &[12, 102]
+        );
+        assert!(processor.process(encoding, Some(pair), true).is_err());
+    }
+
+    #[test]
+    fn serialization() {
+        let processor = bert_builder().build().unwrap();
+        assert_eq!(
+            processor.get_pair().unwrap().to_string(),
+            "[CLS]:0 $A:0 [SEP]:0 $B:1 [SEP]:1"
+        );
+
+        let serialized = serde_json::to_string(&processor).unwrap();
+        let deserialized: TemplateProcessing = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.get_single(), processor.get_single());
+        assert_eq!(deserialized.get_pair(), processor.get_pair());
+
+        // The special tokens are validated too
+        let serialized = serialized.replace(r#""[SEP]":102"#, r#""[EOS]":102"#);
+        let err = serde_json::from_str::<TemplateProcessing>(&serialized)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("[SEP]"));
+    }
+}
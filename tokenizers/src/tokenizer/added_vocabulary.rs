@@ -12,13 +12,25 @@ pub struct AddedToken {
     /// The content of the added token
     pub content: String,
     /// Whether this token must be a single word or can break words
+    #[serde(default)]
     pub single_word: bool,
     /// Whether this token should strip whitespaces on its left
+    #[serde(default)]
     pub lstrip: bool,
     /// Whether this token should strip whitespaces on its right
+    #[serde(default)]
     pub rstrip: bool,
     /// Whether this token should be normalized
+    #[serde(default = "default_normalized")]
     pub normalized: bool,
+    /// Whether this token should match its content without considering the case. The
+    /// matched text is kept as is, with its original case.
+    #[serde(default)]
+    pub lowercase: bool,
+}
+
+fn default_normalized() -> bool {
+    true
 }
 
 impl AddedToken {
@@ -55,6 +67,12 @@ impl AddedToken {
         self.normalized = normalized;
         self
     }
+    /// Specify whether this token should match case-insensitively, while keeping the
+    /// case of the matched text.
+    pub fn lowercase(mut self, lowercase: bool) -> Self {
+        self.lowercase = lowercase;
+        self
+    }
     /// Retrive the pattern built for this token, according to all the specified parameters.
     pub fn get_pattern(&self, normalizer: Option<&dyn Normalizer>) -> String {
         let mut r = if self.single_word {
@@ -99,6 +117,10 @@ impl AddedToken {
             r = format!(r"{}(\s)?", r);
         }
 
+        if self.lowercase {
+            r = format!(r"(?i){}", r);
+        }
+
         r
     }
 }
@@ -109,7 +131,8 @@ impl Default for AddedToken {
             single_word: false,
             lstrip: false,
             rstrip: false,
-            normalized: true,
+            normalized: default_normalized(),
+            lowercase: false,
         }
     }
 }
@@ -206,6 +229,14 @@ impl AddedVocabulary {
             .or_else(|| model.id_to_token(id))
     }
 
+    /// Get the content of all the special tokens, in the order they were added
+    pub fn get_special_tokens(&self) -> Vec<&str> {
+        self.special_tokens
+            .iter()
+            .map(|t| t.content.as_str())
+            .collect()
+    }
+
     /// Check if a token is a special token
     pub fn is_special_token(&self, token: &str) -> bool {
         self.special_tokens_set.contains(token)
@@ -242,6 +273,7 @@ impl AddedVocabulary {
                 continue;
             }
 
+            // A token already in the vocabulary (added or from the model) keeps its id
             let id = if let Some(id) = self.token_to_id(&token.content, model) {
                 ignored += 1;
                 id
@@ -320,16 +352,14 @@ impl AddedVocabulary {
             })
             .collect::<Vec<_>>();
 
-        // We sort all the matches by their start and then by their pattern id
-        matches.sort_by(
-            |(idxa, (sa, _)), (idxb, (sb, _))| {
-                if sa != sb {
-                    sa.cmp(sb)
-                } else {
-                    idxa.cmp(idxb)
-                }
-            },
-        );
+        // We sort all the matches by their start, then the longest first, and then by their
+        // pattern id
+        matches.sort_by(|(idxa, (sa, ea)), (idxb, (sb, eb))| {
+            sa.cmp(sb).then(eb.cmp(ea)).then(idxa.cmp(idxb))
+        });
+        // When several tokens match at the same position, like `New` and `New York`, the
+        // longest one wins
+        matches.dedup_by_key(|(_, (start, _))| *start);
 
         // Select the matches (if some are overlapping) we want to keep
         let mut i = 0;
@@ -414,6 +444,11 @@ impl AddedVocabulary {
     /// input sentence `I read a book Yesterday`, if the normalizer is supposed to lowercase
     /// everything, we expect a match.
     ///
+    /// This is done in a single pass: tokens with `normalized == false` are matched on the raw
+    /// input only, and tokens with `normalized == true` on the normalized pieces only. Each piece
+    /// gets normalized once, and never gets split again afterwards, so a normalizer producing
+    /// the content of a non-normalized token does not lead to a match.
+    ///
     /// This method returns a `Vec` of `(NormalizedString, Option<u32>)`, where the optional `u32`
     /// contains the relevant ID if this is an additional token.
     pub fn extract_and_normalize(
@@ -448,6 +483,10 @@ pub(super) struct AddedTokenWithId {
     pub id: u32,
     /// Whether this is a special token
     pub special: bool,
+    /// Whether the token should be normalized, as found while deserializing. It is missing from
+    /// files that were saved before it existed, and then defaults to `!special`.
+    #[serde(default, skip_serializing)]
+    pub normalized: Option<bool>,
 
     #[serde(flatten)]
     /// The target AddedToken
@@ -465,6 +504,7 @@ impl Serialize for AddedVocabulary {
             .map(|(id, token)| AddedTokenWithId {
                 id: *id,
                 special: self.special_tokens_set.contains(&token.content),
+                normalized: None,
                 token: token.clone(),
             })
             .collect::<Vec<_>>();
@@ -686,4 +726,97 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn single_word_boundaries() {
+        let model = ModelMock::new(&[]);
+        let mut vocab = AddedVocabulary::new();
+        vocab.add_tokens(
+            &[AddedToken::from("ing", false).single_word(true)],
+            &model,
+            None,
+        );
+
+        let result = vocab.extract_and_normalize(None, "singing ing éing ing!");
+        assert_eq!(
+            result
+                .iter()
+                .map(|(normalized, id)| (normalized.get_original(), *id))
+                .collect::<Vec<_>>(),
+            vec![
+                // Never matched inside a word, even next to a non-ascii letter
+                ("singing ", None),
+                ("ing", Some(0)),
+                (" éing ", None),
+                ("ing", Some(0)),
+                ("!", None)
+            ]
+        );
+    }
+
+    #[test]
+    fn normalized_pieces_are_not_split_again() {
+        let model = ModelMock::new(&[]);
+        let normalizer = Lowercase;
+        let mut vocab = AddedVocabulary::new();
+        vocab.add_tokens(
+            &[AddedToken::from("hello", false).normalized(false)],
+            &model,
+            Some(&normalizer),
+        );
+
+        let result = vocab.extract_and_normalize(Some(&normalizer), "HELLO hello");
+        assert_eq!(
+            result
+                .iter()
+                .map(|(normalized, id)| (normalized.get(), *id))
+                .collect::<Vec<_>>(),
+            vec![
+                // Lowercased into `hello`, but only matched on the raw input
+                ("hello ", None),
+                ("hello", Some(0))
+            ]
+        );
+    }
+
+    #[test]
+    fn normalized_defaults() {
+        let model = ModelMock::new(&[]);
+        let normalizer = Lowercase;
+        let mut vocab = AddedVocabulary::new();
+
+        let special = AddedToken::from("[MASK]", true);
+        let added = AddedToken::from("yesterday", false);
+        assert!(!special.normalized);
+        assert!(added.normalized);
+
+        vocab.add_special_tokens(&[special], &model, Some(&normalizer));
+        vocab.add_tokens(&[added], &model, Some(&normalizer));
+
+        let result = vocab.extract_and_normalize(Some(&normalizer), "[MASK] Yesterday");
+        assert_eq!(
+            result
+                .iter()
+                .map(|(normalized, id)| (normalized.get(), *id))
+                .collect::<Vec<_>>(),
+            vec![
+                // Special tokens are matched on the raw text
+                ("[MASK]", Some(0)),
+                (" ", None),
+                // Other tokens after normalization
+                ("yesterday", Some(1))
+            ]
+        );
+    }
+
+    #[test]
+    fn deserialize_missing_flags() {
+        let token: AddedToken = serde_json::from_str(r#"{"content":"[MASK]"}"#).unwrap();
+        assert_eq!(token.content, "[MASK]");
+        assert!(!token.single_word && !token.lstrip && !token.rstrip && !token.lowercase);
+        assert!(token.normalized);
+
+        // The content is required
+        assert!(serde_json::from_str::<AddedToken>("{}").is_err());
+    }
 }
@@ -1,8 +1,80 @@
 use crate::parallelism::*;
-use crate::tokenizer::{Offsets, Token};
+use crate::tokenizer::{Offsets, Result, Token};
 use crate::utils::padding::PaddingDirection;
+use crate::utils::truncation::TruncationDirection;
 use serde::{Deserialize, Serialize};
 
+/// The markers that models add to the tokens, with no counterpart in the original string
+const TOKEN_MARKERS: [&str; 2] = ["##", "</w>"];
+/// The chars used by the pre-tokenizers to represent some whitespace
+const WHITESPACE_MARKERS: [char; 5] = ['Ġ', 'Ċ', 'č', 'ĉ', '▁'];
+
+#[derive(Debug)]
+pub enum OffsetsError {
+    /// The offsets of the token don't fit in the original string
+    OutOfBounds { token: usize, offsets: Offsets },
+    /// A special token added by the post-processor covers some of the original string
+    SpecialNotEmpty { token: usize, offsets: Offsets },
+    /// The original string at the offsets of the token doesn't contain the token
+    Mismatch {
+        token: usize,
+        value: String,
+        original: String,
+    },
+    /// The words returned by the pre-tokenizer are out of bounds, or overlap each other
+    PreTokenized {
+        pre_tokenizer: String,
+        word: usize,
+        offsets: Offsets,
+    },
+    /// The offsets of the words given with a pre-tokenized input are out of bounds, or overlap
+    /// each other
+    Input { word: usize, offsets: Offsets },
+}
+
+impl std::fmt::Display for OffsetsError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            OffsetsError::OutOfBounds { token, offsets } => write!(
+                fmt,
+                "Offsets error: Offsets {:?} of token {} are out of bounds",
+                offsets, token
+            ),
+            OffsetsError::SpecialNotEmpty { token, offsets } => write!(
+                fmt,
+                "Offsets error: Special token {} should have empty offsets, got {:?}",
+                token, offsets
+            ),
+            OffsetsError::Mismatch {
+                token,
+                value,
+                original,
+            } => write!(
+                fmt,
+                "Offsets error: Token {} ({:?}) doesn't match the original {:?}",
+                token, value, original
+            ),
+            OffsetsError::PreTokenized {
+                pre_tokenizer,
+                word,
+                offsets,
+            } => write!(
+                fmt,
+                "Offsets error: Offsets {:?} of word {} returned by {} are out of bounds or \
+                 overlap the previous word",
+                offsets, word, pre_tokenizer
+            ),
+            OffsetsError::Input { word, offsets } => write!(
+                fmt,
+                "Offsets error: Offsets {:?} of word {} in the input are out of bounds or \
+                 overlap the previous word",
+                offsets, word
+            ),
+        }
+    }
+}
+impl std::error::Error for OffsetsError {}
+
 /// Represents the output of a `Tokenizer`.
 #[derive(Default, PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub struct Encoding {
@@ -21,7 +93,14 @@ pub struct Encoding {
     /// Mask identifying padding tokens for the attention mechanism
     attention_mask: Vec<u32>,
     /// A list of overflowing Encoding generated when we got truncated
+    #[serde(default)]
     overflowing: Vec<Encoding>,
+    /// Offsets of the token/ID in the normalized string, only when asked for
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    normalized_offsets: Vec<Offsets>,
+    /// Mask identifying the tokens emitted as unknown by the model, only when asked for
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    unk_mask: Vec<bool>,
 }
 impl Encoding {
     #[allow(clippy::too_many_arguments)]
@@ -44,6 +123,8 @@ impl Encoding {
             special_tokens_mask,
             attention_mask,
             overflowing,
+            normalized_offsets: vec![],
+            unk_mask: vec![],
         }
     }
 
@@ -74,6 +155,8 @@ impl Encoding {
             attention_mask: vec![1; length],
             special_tokens_mask: vec![0; length],
             overflowing: vec![],
+            normalized_offsets: vec![],
+            unk_mask: vec![],
         }
     }
 
@@ -97,14 +180,37 @@ impl Encoding {
         &mut self.words
     }
 
+    /// The index of the word each token comes from, with `None` for any special token,
+    /// including the padding
+    pub fn get_word_ids(&self) -> Vec<Option<u32>> {
+        self.words
+            .iter()
+            .zip(&self.special_tokens_mask)
+            .map(|(word, special)| if *special == 1 { None } else { *word })
+            .collect()
+    }
+
     pub fn get_ids(&self) -> &[u32] {
         &self.ids
     }
 
+    /// Consume the `Encoding`, keeping only its ids
+    pub(crate) fn into_ids(self) -> Vec<u32> {
+        self.ids
+    }
+
     pub fn get_type_ids(&self) -> &[u32] {
         &self.type_ids
     }
 
+    /// Set the same type id on every token, including the ones of the overflowing encodings
+    pub fn set_type_ids(&mut self, type_id: u32) {
+        self.type_ids = vec![type_id; self.type_ids.len()];
+        self.overflowing
+            .iter_mut()
+            .for_each(|encoding| encoding.set_type_ids(type_id));
+    }
+
     pub fn get_offsets(&self) -> &[Offsets] {
         &self.offsets
     }
@@ -113,6 +219,30 @@ impl Encoding {
         &mut self.offsets
     }
 
+    /// Get the offsets of each token in the normalized string, when kept using
+    /// `EncodeOptions::keep_normalized_offsets`. Empty otherwise.
+    pub fn get_normalized_offsets(&self) -> &[Offsets] {
+        &self.normalized_offsets
+    }
+
+    /// Set the offsets of each token in the normalized string. They must be empty, or have one
+    /// entry per token.
+    pub fn set_normalized_offsets(&mut self, normalized_offsets: Vec<Offsets>) {
+        self.normalized_offsets = normalized_offsets;
+    }
+
+    /// Get a mask of the tokens emitted as unknown by the model, when kept using
+    /// `EncodeOptions::keep_unk_mask`. Empty otherwise.
+    pub fn get_unk_mask(&self) -> &[bool] {
+        &self.unk_mask
+    }
+
+    /// Set the mask of the tokens emitted as unknown by the model. It must be empty, or have
+    /// one entry per token.
+    pub fn set_unk_mask(&mut self, unk_mask: Vec<bool>) {
+        self.unk_mask = unk_mask;
+    }
+
     pub fn get_special_tokens_mask(&self) -> &[u32] {
         &self.special_tokens_mask
     }
@@ -121,6 +251,25 @@ impl Encoding {
         &self.attention_mask
     }
 
+    /// Mask out any token whose id is not attended according to the given predicate. Padding
+    /// already sets the attention mask to 0 for the padding tokens, and these stay masked: this
+    /// lets us additionally mask some chosen ids, like a sentinel token.
+    /// This also applies to the overflowing encodings.
+    pub fn recompute_attention_mask(&mut self, is_attended: impl Fn(u32) -> bool) {
+        self.mask_unattended(&is_attended);
+    }
+
+    fn mask_unattended(&mut self, is_attended: &dyn Fn(u32) -> bool) {
+        for encoding in self.overflowing.iter_mut() {
+            encoding.mask_unattended(is_attended);
+        }
+        for (mask, id) in self.attention_mask.iter_mut().zip(&self.ids) {
+            if !is_attended(*id) {
+                *mask = 0;
+            }
+        }
+    }
+
     pub fn get_overflowing(&self) -> &Vec<Encoding> {
         &self.overflowing
     }
@@ -195,7 +344,186 @@ impl Encoding {
             .flatten()
     }
 
-    /// Truncate the current `Encoding`.
+    /// Check that the offsets of each token are consistent with the given original string,
+    /// returning the first inconsistency, if any. The original string at the offsets of a
+    /// token must contain this token, ignoring the case, the whitespaces, and the markers added
+    /// by the models and pre-tokenizers (like `##` or `Ġ`). The special tokens added by the
+    /// post-processor must have empty offsets, and the padding is ignored. This also checks
+    /// the overflowing encodings.
+    ///
+    /// This is meant to help debugging: tokens altered by the normalizer beyond the case, or
+    /// mapped to bytes by the `ByteLevel` pre-tokenizer, are reported as inconsistent.
+    pub fn validate_offsets(&self, original: &str) -> Result<()> {
+        self.validate_offsets_chars(&original.chars().collect::<Vec<_>>())
+    }
+
+    fn validate_offsets_chars(&self, original: &[char]) -> Result<()> {
+        let clean = |s: &str| {
+            TOKEN_MARKERS
+                .iter()
+                .fold(s.to_owned(), |s, marker| s.replace(marker, ""))
+                .chars()
+                .filter(|c| !c.is_whitespace() && !WHITESPACE_MARKERS.contains(c))
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        };
+
+        for (i, &(start, end)) in self.offsets.iter().enumerate() {
+            if self.attention_mask[i] == 0 {
+                continue;
+            }
+            if self.special_tokens_mask[i] == 1 {
+                if start != end {
+                    return Err(Box::new(OffsetsError::SpecialNotEmpty {
+                        token: i,
+                        offsets: (start, end),
+                    }));
+                }
+                continue;
+            }
+            if start > end || end > original.len() {
+                return Err(Box::new(OffsetsError::OutOfBounds {
+                    token: i,
+                    offsets: (start, end),
+                }));
+            }
+
+            let slice = original[start..end].iter().collect::<String>();
+            if !clean(&slice).contains(&clean(&self.tokens[i])) {
+                return Err(Box::new(OffsetsError::Mismatch {
+                    token: i,
+                    value: self.tokens[i].clone(),
+                    original: slice,
+                }));
+            }
+        }
+
+        self.overflowing
+            .iter()
+            .try_for_each(|encoding| encoding.validate_offsets_chars(original))
+    }
+
+    /// Truncate the current `Encoding`, removing tokens from the given direction. The removed
+    /// tokens are kept in the overflowing encodings, the nearest ones first.
+    ///
+    /// Panic if `stride >= max_len` or `max_len == 0`.
+    pub fn truncate_with_direction(
+        &mut self,
+        max_len: usize,
+        stride: usize,
+        direction: TruncationDirection,
+    ) {
+        match direction {
+            TruncationDirection::Right => self.truncate(max_len, stride),
+            TruncationDirection::Left => {
+                // Truncating on the left is just like truncating a reversed encoding
+                // on the right
+                self.reverse();
+                self.truncate(max_len, stride);
+                self.reverse();
+            }
+        }
+    }
+
+    /// Truncate the current `Encoding` just like `truncate_with_direction`, but without cutting
+    /// any word across two windows, when possible. Each window ends on a word boundary, and the
+    /// next one starts on the first word boundary within the last `stride` tokens. A word that
+    /// doesn't fit in `max_len` tokens still gets cut, as there is no other way.
+    pub fn truncate_at_words(
+        &mut self,
+        max_len: usize,
+        stride: usize,
+        direction: TruncationDirection,
+    ) {
+        match direction {
+            TruncationDirection::Right => self.truncate_at_words_right(max_len, stride),
+            TruncationDirection::Left => {
+                self.reverse();
+                self.truncate_at_words_right(max_len, stride);
+                self.reverse();
+            }
+        }
+    }
+
+    fn truncate_at_words_right(&mut self, max_len: usize, stride: usize) {
+        let len = self.ids.len();
+        if max_len >= len {
+            return;
+        }
+        assert!(max_len > 0);
+        assert!(stride < max_len);
+
+        let words = &self.words;
+        let is_boundary =
+            |i: usize| i == 0 || i == len || words[i - 1].is_none() || words[i - 1] != words[i];
+
+        let mut windows = vec![];
+        let (mut start, mut prev_end) = (0, 0);
+        loop {
+            let mut end = (start + max_len).min(len);
+            if end < len {
+                if let Some(boundary) = (prev_end + 1..=end).rev().find(|i| is_boundary(*i)) {
+                    end = boundary;
+                }
+            }
+            windows.push(start..end);
+            if end == len {
+                break;
+            }
+
+            let min_start = end.saturating_sub(stride).max(start + 1);
+            start = (min_start..=end).find(|i| is_boundary(*i)).unwrap_or(end);
+            prev_end = end;
+        }
+
+        let mut windows = windows.into_iter().map(|range| self.slice(range));
+        let mut encoding = windows.next().unwrap();
+        encoding.overflowing = windows.collect();
+        *self = encoding;
+    }
+
+    /// Build a new `Encoding` with the tokens in the given range, without any overflowing.
+    /// The offsets are kept as they are, still relative to the original sequence.
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn slice(&self, range: std::ops::Range<usize>) -> Encoding {
+        Encoding {
+            ids: self.ids[range.clone()].to_vec(),
+            type_ids: self.type_ids[range.clone()].to_vec(),
+            tokens: self.tokens[range.clone()].to_vec(),
+            words: self.words[range.clone()].to_vec(),
+            offsets: self.offsets[range.clone()].to_vec(),
+            special_tokens_mask: self.special_tokens_mask[range.clone()].to_vec(),
+            attention_mask: self.attention_mask[range.clone()].to_vec(),
+            overflowing: vec![],
+            normalized_offsets: if self.normalized_offsets.is_empty() {
+                vec![]
+            } else {
+                self.normalized_offsets[range.clone()].to_vec()
+            },
+            unk_mask: if self.unk_mask.is_empty() {
+                vec![]
+            } else {
+                self.unk_mask[range].to_vec()
+            },
+        }
+    }
+
+    /// Reverse the order of all the tokens, including in the overflowing encodings
+    fn reverse(&mut self) {
+        self.ids.reverse();
+        self.type_ids.reverse();
+        self.tokens.reverse();
+        self.words.reverse();
+        self.offsets.reverse();
+        self.special_tokens_mask.reverse();
+        self.attention_mask.reverse();
+        self.normalized_offsets.reverse();
+        self.unk_mask.reverse();
+        self.overflowing.iter_mut().for_each(|e| e.reverse());
+    }
+
+    /// Truncate the current `Encoding`, removing tokens from the right.
     ///
     /// Panic if `stride >= max_len` or `max_len == 0`.
     pub fn truncate(&mut self, max_len: usize, stride: usize) {
@@ -213,6 +541,16 @@ impl Encoding {
         let o_offsets = self.offsets.split_off(max_len);
         let o_spe_toks = self.special_tokens_mask.split_off(max_len);
         let o_attent = self.attention_mask.split_off(max_len);
+        let o_norm_offsets = if self.normalized_offsets.is_empty() {
+            vec![]
+        } else {
+            self.normalized_offsets.split_off(max_len)
+        };
+        let o_unk_mask = if self.unk_mask.is_empty() {
+            vec![]
+        } else {
+            self.unk_mask.split_off(max_len)
+        };
 
         // Now we need to separate the overflowing part into as many Encoding as needed
         assert!(stride < max_len);
@@ -265,6 +603,28 @@ impl Encoding {
                     stride,
                 ),
                 overflowing: vec![],
+                normalized_offsets: if o_norm_offsets.is_empty() {
+                    vec![]
+                } else {
+                    get_current_part(
+                        &prev_encoding.normalized_offsets,
+                        &o_norm_offsets,
+                        part_size,
+                        part_id,
+                        stride,
+                    )
+                },
+                unk_mask: if o_unk_mask.is_empty() {
+                    vec![]
+                } else {
+                    get_current_part(
+                        &prev_encoding.unk_mask,
+                        &o_unk_mask,
+                        part_size,
+                        part_id,
+                        stride,
+                    )
+                },
             };
 
             part_id += 1;
@@ -292,27 +652,36 @@ impl Encoding {
     }
 
     /// Merge ourself with the given `Encoding`. Happens in place.
-    pub fn merge_with(&mut self, pair: Encoding, growing_offsets: bool) {
+    ///
+    /// The overflowing parts are combined too, so that none of them gets dropped. The resulting
+    /// overflowing encodings are, in this order:
+    ///   - Each of our overflowing with the pair itself
+    ///   - Each of our overflowing with each of the pair's overflowing
+    ///   - Ourself with each of the pair's overflowing
+    /// None of them has any overflowing of its own.
+    pub fn merge_with(&mut self, mut pair: Encoding, growing_offsets: bool) {
         // Handle merging the overflowing parts too: Combine them all
         // In most of the cases, we expect `pair.overflowing.len() == 0`
+        let self_overflowing = self.take_overflowing();
+        let pair_overflowing = pair.take_overflowing();
         let mut overflowings = vec![];
 
         // 1. All our overflowings with all the others
-        for self_o in &self.overflowing {
+        for self_o in &self_overflowing {
             // 1. The pair itself
             let mut n_encoding = self_o.clone();
             n_encoding.merge_with(pair.clone(), growing_offsets);
             overflowings.push(n_encoding);
 
             // 2. Its overflowings (this should rarely happen...)
-            for other_o in &pair.overflowing {
+            for other_o in &pair_overflowing {
                 let mut n_encoding = self_o.clone();
                 n_encoding.merge_with(other_o.clone(), growing_offsets);
                 overflowings.push(n_encoding);
             }
         }
         // 2. Ourself with all the other overflowings (this should rarely happen too...)
-        for other_o in &pair.overflowing {
+        for other_o in &pair_overflowing {
             let mut n_encoding = self.clone();
             n_encoding.merge_with(other_o.clone(), growing_offsets);
             overflowings.push(n_encoding);
@@ -348,6 +717,27 @@ impl Encoding {
                 .map(|(start, end)| (start + starting_offset, end + starting_offset))
                 .collect::<Vec<_>>(),
         );
+        // Normalized offsets are kept only if at least one of the two has them, the other one
+        // gets empty offsets. They are relative to the normalized string of each sequence.
+        if !self.normalized_offsets.is_empty() || !pair.normalized_offsets.is_empty() {
+            if self.normalized_offsets.is_empty() {
+                self.normalized_offsets = vec![(0, 0); self.special_tokens_mask.len()];
+            }
+            if pair.normalized_offsets.is_empty() {
+                pair.normalized_offsets = vec![(0, 0); pair.special_tokens_mask.len()];
+            }
+            self.normalized_offsets.extend(pair.normalized_offsets);
+        }
+        // Same for the unk mask, where the other one gets only known tokens
+        if !self.unk_mask.is_empty() || !pair.unk_mask.is_empty() {
+            if self.unk_mask.is_empty() {
+                self.unk_mask = vec![false; self.special_tokens_mask.len()];
+            }
+            if pair.unk_mask.is_empty() {
+                pair.unk_mask = vec![false; pair.special_tokens_mask.len()];
+            }
+            self.unk_mask.extend(pair.unk_mask);
+        }
         self.special_tokens_mask.extend(pair.special_tokens_mask);
         self.attention_mask.extend(pair.attention_mask);
         self.overflowing = overflowings;
@@ -403,6 +793,18 @@ impl Encoding {
                     .map(|_| (0, 0))
                     .chain(self.offsets.drain(..))
                     .collect();
+                if !self.normalized_offsets.is_empty() {
+                    self.normalized_offsets = (0..pad_length)
+                        .map(|_| (0, 0))
+                        .chain(self.normalized_offsets.drain(..))
+                        .collect();
+                }
+                if !self.unk_mask.is_empty() {
+                    self.unk_mask = (0..pad_length)
+                        .map(|_| false)
+                        .chain(self.unk_mask.drain(..))
+                        .collect();
+                }
             }
             PaddingDirection::Right => {
                 self.ids.extend((0..pad_length).map(|_| pad_id));
@@ -413,6 +815,13 @@ impl Encoding {
                 self.attention_mask.extend((0..pad_length).map(|_| 0));
                 self.special_tokens_mask.extend((0..pad_length).map(|_| 1));
                 self.offsets.extend((0..pad_length).map(|_| (0, 0)));
+                if !self.normalized_offsets.is_empty() {
+                    self.normalized_offsets
+                        .extend((0..pad_length).map(|_| (0, 0)));
+                }
+                if !self.unk_mask.is_empty() {
+                    self.unk_mask.extend((0..pad_length).map(|_| false));
+                }
             }
         }
     }
@@ -450,6 +859,8 @@ mod tests {
             special_tokens_mask: vec![0],
             attention_mask: vec![1],
             overflowing: vec![],
+            normalized_offsets: vec![],
+            unk_mask: vec![],
         };
         let b = Encoding {
             ids: vec![2],
@@ -460,6 +871,8 @@ mod tests {
             special_tokens_mask: vec![0],
             attention_mask: vec![1],
             overflowing: vec![],
+            normalized_offsets: vec![],
+            unk_mask: vec![],
         };
         a.merge_with(b, true);
 
@@ -474,10 +887,59 @@ mod tests {
                 special_tokens_mask: vec![0, 0],
                 attention_mask: vec![1, 1],
                 overflowing: vec![],
+                normalized_offsets: vec![],
+                unk_mask: vec![],
             }
         );
     }
 
+    #[test]
+    fn merge_overflowing() {
+        let encoding = |ids: Vec<u32>| Encoding {
+            type_ids: vec![0; ids.len()],
+            tokens: ids.iter().map(|id| id.to_string()).collect(),
+            words: ids.iter().map(|id| Some(*id)).collect(),
+            offsets: ids
+                .iter()
+                .map(|id| (*id as usize, *id as usize + 1))
+                .collect(),
+            special_tokens_mask: vec![0; ids.len()],
+            attention_mask: vec![1; ids.len()],
+            ids,
+            overflowing: vec![],
+            normalized_offsets: vec![],
+            unk_mask: vec![],
+        };
+
+        // Only one side overflowing
+        let mut a = encoding(vec![1, 2]);
+        a.overflowing = vec![encoding(vec![3])];
+        a.merge_with(encoding(vec![4]), false);
+        assert_eq!(a.get_ids(), &[1, 2, 4]);
+        assert_eq!(a.get_overflowing().len(), 1);
+        assert_eq!(a.get_overflowing()[0].get_ids(), &[3, 4]);
+        assert!(a.get_overflowing()[0].get_overflowing().is_empty());
+
+        // Both sides overflowing
+        let mut a = encoding(vec![1, 2]);
+        a.overflowing = vec![encoding(vec![3])];
+        let mut b = encoding(vec![4, 5]);
+        b.overflowing = vec![encoding(vec![6])];
+        a.merge_with(b, false);
+        assert_eq!(a.get_ids(), &[1, 2, 4, 5]);
+        assert_eq!(
+            a.get_overflowing()
+                .iter()
+                .map(|o| o.get_ids())
+                .collect::<Vec<_>>(),
+            vec![&[3, 4, 5][..], &[3, 6][..], &[1, 2, 6][..]]
+        );
+        assert!(a
+            .get_overflowing()
+            .iter()
+            .all(|o| o.get_overflowing().is_empty()));
+    }
+
     #[test]
     fn truncate() {
         let mut a = Encoding {
@@ -493,6 +955,8 @@ mod tests {
             special_tokens_mask: vec![0, 0, 0],
             attention_mask: vec![1, 1, 1],
             overflowing: vec![],
+            normalized_offsets: vec![],
+            unk_mask: vec![],
         };
         a.truncate(2, 0);
 
@@ -515,11 +979,38 @@ mod tests {
                     special_tokens_mask: vec![0],
                     attention_mask: vec![1],
                     overflowing: vec![],
-                }]
+                    normalized_offsets: vec![],
+                    unk_mask: vec![],
+                }],
+                normalized_offsets: vec![],
+                unk_mask: vec![],
             }
         );
     }
 
+    #[test]
+    fn recompute_attention_mask() {
+        let mut encoding = Encoding {
+            ids: vec![1, 2, 42, 3],
+            attention_mask: vec![1, 1, 1, 1],
+            overflowing: vec![Encoding {
+                ids: vec![42, 4],
+                attention_mask: vec![1, 1],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        encoding.pad(6, 0, 0, "[PAD]", PaddingDirection::Right);
+        assert_eq!(encoding.get_attention_mask(), &[1, 1, 1, 1, 0, 0]);
+
+        encoding.recompute_attention_mask(|id| id != 42);
+        assert_eq!(encoding.get_attention_mask(), &[1, 1, 0, 1, 0, 0]);
+        assert_eq!(
+            encoding.get_overflowing()[0].get_attention_mask(),
+            &[0, 1, 0, 0, 0, 0]
+        );
+    }
+
     #[test]
     fn mappings() {
         let encoding = Encoding {
@@ -577,4 +1068,124 @@ mod tests {
         assert_eq!(encoding.char_to_word(16), None);
         assert_eq!(encoding.char_to_word(23), Some(3));
     }
+
+    #[test]
+    fn truncate_at_words() {
+        let mut encoding = Encoding::from_tokens(
+            [0, 0, 1, 1, 1, 2, 3, 3]
+                .iter()
+                .enumerate()
+                .map(|(i, w)| Token::new(i as u32, i.to_string(), (i, i + 1), *w))
+                .collect(),
+            0,
+        );
+        encoding.truncate_at_words(4, 2, TruncationDirection::Right);
+
+        let words = |e: &Encoding| e.get_words().iter().map(|w| w.unwrap()).collect::<Vec<_>>();
+        assert_eq!(words(&encoding), [0, 0]);
+        assert_eq!(
+            encoding
+                .get_overflowing()
+                .iter()
+                .map(words)
+                .collect::<Vec<_>>(),
+            [vec![1, 1, 1, 2], vec![2, 3, 3]]
+        );
+    }
+
+    #[test]
+    fn validate_offsets() {
+        let original = "Hello, y'all!";
+        let mut encoding = Encoding::new(
+            vec![0, 1, 2, 3, 4, 5, 6, 7],
+            vec![0; 8],
+            vec![
+                "[CLS]".into(),
+                "Ġhello".into(),
+                ",".into(),
+                "Ġy".into(),
+                "'".into(),
+                "##all".into(),
+                "!".into(),
+                "[PAD]".into(),
+            ],
+            vec![
+                None,
+                Some(0),
+                Some(1),
+                Some(2),
+                Some(3),
+                Some(4),
+                Some(5),
+                None,
+            ],
+            vec![
+                (0, 0),
+                (0, 5),
+                (5, 6),
+                (7, 8),
+                (8, 9),
+                (9, 12),
+                (12, 13),
+                (0, 0),
+            ],
+            vec![1, 0, 0, 0, 0, 0, 0, 1],
+            vec![1, 1, 1, 1, 1, 1, 1, 0],
+            vec![],
+        );
+        assert!(encoding.validate_offsets(original).is_ok());
+
+        encoding.get_offsets_mut()[5] = (10, 13);
+        assert_eq!(
+            encoding.validate_offsets(original).unwrap_err().to_string(),
+            "Offsets error: Token 5 (\"##all\") doesn't match the original \"ll!\""
+        );
+
+        encoding.get_offsets_mut()[5] = (9, 14);
+        assert!(encoding.validate_offsets(original).is_err());
+
+        encoding.get_offsets_mut()[5] = (9, 12);
+        encoding.get_offsets_mut()[0] = (0, 1);
+        assert!(encoding.validate_offsets(original).is_err());
+    }
+
+    #[test]
+    fn slice() {
+        let mut encoding = Encoding::from_tokens(
+            (0..10)
+                .map(|i| {
+                    Token::new(
+                        i as u32 + 10,
+                        i.to_string(),
+                        (2 * i, 2 * i + 1),
+                        i as u32 / 2,
+                    )
+                })
+                .collect(),
+            1,
+        );
+        encoding.special_tokens_mask[3] = 1;
+        encoding.attention_mask[4] = 0;
+        encoding.overflowing = vec![encoding.clone()];
+
+        let slice = encoding.slice(3..7);
+        assert_eq!(slice.get_ids(), &[13, 14, 15, 16]);
+        assert_eq!(slice.get_type_ids(), &[1, 1, 1, 1]);
+        assert_eq!(slice.get_tokens(), &["3", "4", "5", "6"]);
+        assert_eq!(slice.get_words(), &[Some(1), Some(2), Some(2), Some(3)]);
+        assert_eq!(slice.get_offsets(), &[(6, 7), (8, 9), (10, 11), (12, 13)]);
+        assert_eq!(slice.get_special_tokens_mask(), &[1, 0, 0, 0]);
+        assert_eq!(slice.get_attention_mask(), &[1, 0, 1, 1]);
+        assert!(slice.get_overflowing().is_empty());
+        assert!(slice.get_normalized_offsets().is_empty());
+
+        assert!(encoding.slice(10..10).is_empty());
+        assert_eq!(
+            encoding.slice(0..10),
+            Encoding {
+                overflowing: vec![],
+                ..encoding.clone()
+            }
+        );
+    }
 }
@@ -10,16 +10,23 @@
 //!   ...).
 
 pub use crate::utils::iter::LinesWithEnding;
-use crate::utils::iter::ResultShunt;
-pub use crate::utils::padding::{pad_encodings, PaddingDirection, PaddingParams, PaddingStrategy};
-pub use crate::utils::truncation::{truncate_encodings, TruncationParams, TruncationStrategy};
+pub use crate::utils::padding::{
+    pad_encodings, PaddingDirection, PaddingError, PaddingParams, PaddingStrategy,
+};
+use crate::utils::truncation::truncate_encodings_at_words;
+pub use crate::utils::truncation::{
+    truncate_encodings, TruncationDirection, TruncationParams, TruncationStrategy,
+};
+pub use crate::utils::vocab::{diff_vocabs, VocabDiff};
+use downcast_rs::{impl_downcast, Downcast};
 use indicatif::{ProgressBar, ProgressStyle};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs::File,
     io::prelude::*,
     io::BufReader,
     path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
 mod added_vocabulary;
@@ -39,9 +46,10 @@ use crate::utils::parallelism::*;
 
 #[typetag::serde(tag = "type")]
 /// Takes care of pre-processing strings.
-pub trait Normalizer: Send + Sync {
+pub trait Normalizer: Send + Sync + Downcast {
     fn normalize(&self, normalized: &mut NormalizedString) -> Result<()>;
 }
+impl_downcast!(Normalizer);
 
 #[typetag::serde(tag = "type")]
 /// The `PreTokenizer` is in charge of doing the pre-segmentation step. It splits the given string
@@ -49,25 +57,63 @@ pub trait Normalizer: Send + Sync {
 /// `NormalizedString`. In some occasions, the `PreTokenizer` might need to modify the given
 /// `NormalizedString` to ensure we can entirely keep track of the offsets and the mapping with
 /// the original string.
-pub trait PreTokenizer: Send + Sync {
+pub trait PreTokenizer: Send + Sync + Downcast {
     fn pre_tokenize(&self, normalized: &mut NormalizedString) -> Result<Vec<(String, Offsets)>>;
+    /// Pre tokenize a piece of an already pre-tokenized sequence that is not its first piece.
+    /// `PreTokenizer`s adding a prefix space should only do it on the first piece, and can
+    /// override this to skip it.
+    fn pre_tokenize_continuation(
+        &self,
+        normalized: &mut NormalizedString,
+    ) -> Result<Vec<(String, Offsets)>> {
+        self.pre_tokenize(normalized)
+    }
 }
+impl_downcast!(PreTokenizer);
 
 #[typetag::serde(tag = "type")]
 /// Represents a model used during Tokenization (like BPE or Word or Unigram).
-pub trait Model: Send + Sync {
+pub trait Model: Send + Sync + Downcast {
     fn tokenize(&self, tokens: Vec<(String, Offsets)>) -> Result<Vec<Token>>;
+    /// Tokenize words that each carry the index of the word they come from, so that pieces
+    /// of a word that has been split further during pre-tokenization keep the same
+    /// `Token.word`. The `Tokenizer` uses it to number the words across a whole sequence.
+    /// By default, this relies on `tokenize` using the position of each word in its input as
+    /// `Token.word`, which is the case for all the provided models, and returns an
+    /// `UnknownWordError` for any token from a word it was not given.
+    fn tokenize_words(&self, words: Vec<(String, Offsets, u32)>) -> Result<Vec<Token>> {
+        let (tokens, word_ids): (Vec<_>, Vec<_>) = words
+            .into_iter()
+            .map(|(word, offsets, word_id)| ((word, offsets), word_id))
+            .unzip();
+        let mut tokens = self.tokenize(tokens)?;
+        for token in &mut tokens {
+            token.word = *word_ids
+                .get(token.word as usize)
+                .ok_or_else(|| UnknownWordError {
+                    word: token.word,
+                    words: word_ids.len(),
+                })?;
+        }
+        Ok(tokens)
+    }
     fn token_to_id(&self, token: &str) -> Option<u32>;
+    /// Whether the given token is part of the vocabulary. Models that can answer this without
+    /// looking up the id should override it.
+    fn contains_token(&self, token: &str) -> bool {
+        self.token_to_id(token).is_some()
+    }
     fn id_to_token(&self, id: u32) -> Option<&str>;
     fn get_vocab(&self) -> &HashMap<String, u32>;
     fn get_vocab_size(&self) -> usize;
     fn save(&self, folder: &Path, name: Option<&str>) -> Result<Vec<PathBuf>>;
 }
+impl_downcast!(Model);
 
 #[typetag::serde(tag = "type")]
 /// A `PostProcessor` has the responsibility to post process an encoded output of the `Tokenizer`.
 /// It adds any special tokens that a language model would require.
-pub trait PostProcessor: Send + Sync {
+pub trait PostProcessor: Send + Sync + Downcast {
     /// Returns the number of tokens that will be added during the processing step
     fn added_tokens(&self, is_pair: bool) -> usize;
     /// Process both encodings and returns a new merged one
@@ -78,6 +124,7 @@ pub trait PostProcessor: Send + Sync {
         add_special_tokens: bool,
     ) -> Result<Encoding>;
 }
+impl_downcast!(PostProcessor);
 impl dyn PostProcessor {
     pub fn default_process(
         mut encoding: Encoding,
@@ -98,6 +145,12 @@ impl dyn PostProcessor {
 /// A `Decoder` has the responsibility to merge the given `Vec<String>` in a `String`.
 pub trait Decoder: Send + Sync {
     fn decode(&self, tokens: Vec<String>) -> Result<String>;
+    /// Decode some groups of tokens, each group being separated from the next one by some
+    /// special tokens that have been removed. By default, the groups are simply concatenated
+    /// and decoded as one.
+    fn decode_groups(&self, groups: Vec<Vec<String>>) -> Result<String> {
+        self.decode(groups.into_iter().flatten().collect())
+    }
 }
 
 /// A `Trainer` has the responsibility to train a model. We feed it with lines/sentences
@@ -105,6 +158,10 @@ pub trait Decoder: Send + Sync {
 pub trait Trainer: Sync {
     /// Whether we should show progress during the training.
     fn should_show_progress(&self) -> bool;
+    /// The style of the progress bar, when shown. Uses the default style if `None`.
+    fn progress_style(&self) -> Option<ProgressStyle> {
+        None
+    }
     /// The actual training method. This will return a new trained Model as well as a list
     /// of `special_tokens` to be added directly to the tokenizer along with the model.
     fn train(&self, words: HashMap<String, u32>) -> Result<(Box<dyn Model>, Vec<AddedToken>)>;
@@ -117,7 +174,11 @@ pub struct Token {
     pub id: u32,
     pub value: String,
     pub offsets: (usize, usize),
+    /// The index of the word this token comes from. This limits the number of words
+    /// in a sequence to `u32::MAX`.
     pub word: u32,
+    /// Whether the model emitted this token for something out of its vocabulary
+    pub unk: bool,
 }
 impl Token {
     pub fn new(id: u32, value: String, offsets: (usize, usize), word: u32) -> Self {
@@ -126,14 +187,37 @@ impl Token {
             value,
             offsets,
             word,
+            unk: false,
+        }
+    }
+
+    /// Same as `new`, for a token emitted for something out of the vocabulary
+    pub fn new_unk(id: u32, value: String, offsets: (usize, usize), word: u32) -> Self {
+        Token {
+            unk: true,
+            ..Token::new(id, value, offsets, word)
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum InputSequence {
     Raw(String),
     PreTokenized(Vec<String>),
+    /// A pre-tokenized sequence given as the original string and the char offsets of each of its
+    /// words, which must be ordered and within the original string. The offsets of the tokens
+    /// are then relative to the original string, instead of to each word.
+    PreTokenizedWithOffsets(String, Vec<Offsets>),
+}
+
+impl InputSequence {
+    /// Whether the sequence is already split in words, with or without their offsets
+    fn is_pre_tokenized(&self) -> bool {
+        matches!(
+            self,
+            InputSequence::PreTokenized(_) | InputSequence::PreTokenizedWithOffsets(..)
+        )
+    }
 }
 
 impl From<String> for InputSequence {
@@ -166,7 +250,7 @@ impl From<&[&str]> for InputSequence {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum EncodeInput {
     Single(InputSequence),
     Dual(InputSequence, InputSequence),
@@ -184,6 +268,197 @@ impl<I1: Into<InputSequence>, I2: Into<InputSequence>> From<(I1, I2)> for Encode
     }
 }
 
+/// Options used by `Tokenizer::encode_with_options`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EncodeOptions {
+    /// Skip the pre-tokenization, giving the whole normalized string to the model, as a single
+    /// word. This is useful to inspect the model on its own, but it may produce very different
+    /// subword splits than the usual pipeline.
+    pub skip_pretokenize: bool,
+    /// Whether the `PostProcessor` should add the special tokens
+    pub add_special_tokens: bool,
+    /// Also keep the offsets of each token in the normalized string, available with
+    /// `Encoding::get_normalized_offsets`
+    pub keep_normalized_offsets: bool,
+    /// Return a `NulByteError` if any token contains a NUL byte, for consumers expecting
+    /// NUL-terminated strings, like C code
+    pub reject_nul_bytes: bool,
+    /// Return an `OverflowError` instead of truncating, when the input has more tokens than
+    /// allowed by the `TruncationParams`. This has no effect without truncation.
+    pub error_on_overflow: bool,
+    /// Also mark the tokens emitted as unknown by the model, available with
+    /// `Encoding::get_unk_mask`
+    pub keep_unk_mask: bool,
+    /// Give each line of the input to the `PreTokenizer` on its own, keeping its line ending,
+    /// just like when counting the words for training. This avoids any skew between training
+    /// and encoding for the pre-tokenizers that depend on the start of the input, like
+    /// `ByteLevel` with `add_prefix_space`.
+    pub split_lines: bool,
+}
+
+/// Options used by `Tokenizer::decode_with_options` and `Tokenizer::decode_batch_with`
+#[derive(Debug, Clone, Default)]
+pub struct DecodeOptions {
+    /// Whether to remove the special tokens
+    pub skip_special_tokens: bool,
+    /// Remove the spaces left before punctuation and some contractions, like in `Hello , I 'm`
+    pub clean_up_tokenization_spaces: bool,
+    /// Some ids to remove, whether they are special tokens or not
+    pub skip_ids: HashSet<u32>,
+}
+
+/// A token contains a NUL byte, while `EncodeOptions::reject_nul_bytes` is set
+#[derive(Debug)]
+pub struct NulByteError {
+    /// The index of the token in the `Encoding`
+    pub token: usize,
+    /// The content of the token
+    pub value: String,
+}
+
+impl std::fmt::Display for NulByteError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            fmt,
+            "Encoding error: Token {} ({:?}) contains a NUL byte",
+            self.token, self.value
+        )
+    }
+}
+impl std::error::Error for NulByteError {}
+
+/// The input has more tokens than allowed by the truncation, while
+/// `EncodeOptions::error_on_overflow` is set
+#[derive(Debug)]
+pub struct OverflowError {
+    /// The number of tokens of the input, without the special tokens
+    pub length: usize,
+    /// The maximum number of tokens allowed, without the special tokens
+    pub max_length: usize,
+}
+
+impl std::fmt::Display for OverflowError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            fmt,
+            "Encoding error: Input has {} tokens, more than the maximum of {}",
+            self.length, self.max_length
+        )
+    }
+}
+impl std::error::Error for OverflowError {}
+
+/// The input has more words than can be indexed by `Token.word`
+#[derive(Debug)]
+pub struct TooManyWordsError {
+    /// The number of words of the input
+    pub words: usize,
+}
+
+impl std::fmt::Display for TooManyWordsError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            fmt,
+            "Encoding error: Input has {} words, more than the maximum of {}",
+            self.words,
+            u32::MAX
+        )
+    }
+}
+impl std::error::Error for TooManyWordsError {}
+
+/// A `Model` returned a token from a word it was not given, in `Model::tokenize_words`
+#[derive(Debug)]
+pub struct UnknownWordError {
+    /// The word of the token, as returned by `Model::tokenize`
+    pub word: u32,
+    /// The number of words given to the model
+    pub words: usize,
+}
+
+impl std::fmt::Display for UnknownWordError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            fmt,
+            "Model error: Token from word {}, while only {} words were given",
+            self.word, self.words
+        )
+    }
+}
+impl std::error::Error for UnknownWordError {}
+
+/// Find the first word whose offsets are not within `0..len`, or overlap the previous word
+fn find_invalid_offsets<'a, I>(offsets: I, len: usize) -> Option<(usize, Offsets)>
+where
+    I: IntoIterator<Item = &'a Offsets>,
+{
+    let mut previous_end = 0;
+    for (word, (start, end)) in offsets.into_iter().enumerate() {
+        if start > end || *start < previous_end || *end > len {
+            return Some((word, (*start, *end)));
+        }
+        previous_end = *end;
+    }
+    None
+}
+
+/// Check that each word of a sequence with this many words can get a `u32` index
+fn check_words_count(words: usize) -> Result<()> {
+    if words > u32::MAX as usize {
+        Err(Box::new(TooManyWordsError { words }))
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum PrefixError {
+    /// The `Encoding` of the prefix contains some special tokens
+    SpecialTokens,
+    /// The last word of the prefix gets tokenized differently when followed by the suffix
+    NotOnBoundary,
+}
+
+impl std::fmt::Display for PrefixError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PrefixError::SpecialTokens => write!(
+                fmt,
+                "Prefix error: The prefix encoding must not contain special tokens"
+            ),
+            PrefixError::NotOnBoundary => write!(
+                fmt,
+                "Prefix error: The prefix doesn't end on a clean pre-token boundary"
+            ),
+        }
+    }
+}
+impl std::error::Error for PrefixError {}
+
+/// A sequence once it went through `Tokenizer::tokenize_sequence`
+#[derive(Default)]
+struct TokenizedSequence {
+    /// The tokens, with their offsets relative to the original string (to each of its pieces
+    /// when pre-tokenized), and their words numbered across the whole sequence
+    tokens: Vec<Token>,
+    /// The offsets of each token in the normalized string, with
+    /// `EncodeOptions::keep_normalized_offsets` only
+    normalized_offsets: Vec<Offsets>,
+    /// The `NormalizedString` of each piece of the sequence, split around the added tokens
+    normalized: Vec<NormalizedString>,
+}
+
+/// Merge the pieces of a sequence back into a single `NormalizedString`
+fn merge_normalized(pieces: Vec<NormalizedString>) -> NormalizedString {
+    // We may receive no piece at all, in which case we just return an empty NormalizedString
+    let mut pieces = pieces.into_iter();
+    let mut normalized = pieces.next().unwrap_or_default();
+    for n in pieces {
+        normalized.merge_with(&n);
+    }
+    normalized
+}
+
 /// A `Tokenizer` is capable of encoding/decoding any text.
 pub struct Tokenizer {
     // Tokenizer parts
@@ -195,12 +470,92 @@ pub struct Tokenizer {
 
     // Added Vocabulary capabilities
     added_vocabulary: AddedVocabulary,
+    added_vocabulary_frozen: bool,
 
     // General processing parameters
     truncation: Option<TruncationParams>,
     padding: Option<PaddingParams>,
 }
 
+/// A `TokenizerBuilder` can be used to create a `Tokenizer` declaratively, including its
+/// added tokens, which can only be registered once the model and the normalizer are set.
+pub struct TokenizerBuilder {
+    tokenizer: Tokenizer,
+    special_tokens: Vec<AddedToken>,
+    added_tokens: Vec<AddedToken>,
+    padding: Option<PaddingParams>,
+}
+
+impl TokenizerBuilder {
+    /// Create a new `TokenizerBuilder` using the given Model
+    pub fn new(model: Box<dyn Model>) -> Self {
+        Self {
+            tokenizer: Tokenizer::new(model),
+            special_tokens: vec![],
+            added_tokens: vec![],
+            padding: None,
+        }
+    }
+
+    /// Set the normalizer
+    pub fn with_normalizer(mut self, normalizer: Box<dyn Normalizer>) -> Self {
+        self.tokenizer.with_normalizer(normalizer);
+        self
+    }
+
+    /// Set the pre tokenizer
+    pub fn with_pre_tokenizer(mut self, pre_tokenizer: Box<dyn PreTokenizer>) -> Self {
+        self.tokenizer.with_pre_tokenizer(pre_tokenizer);
+        self
+    }
+
+    /// Set the post processor
+    pub fn with_post_processor(mut self, post_processor: Box<dyn PostProcessor>) -> Self {
+        self.tokenizer.with_post_processor(post_processor);
+        self
+    }
+
+    /// Set the decoder
+    pub fn with_decoder(mut self, decoder: Box<dyn Decoder>) -> Self {
+        self.tokenizer.with_decoder(decoder);
+        self
+    }
+
+    /// Set the truncation parameters
+    pub fn with_truncation(mut self, trunc: Option<TruncationParams>) -> Self {
+        self.tokenizer.with_truncation(trunc);
+        self
+    }
+
+    /// Set the padding parameters, set when building the `Tokenizer`
+    pub fn with_padding(mut self, padding: Option<PaddingParams>) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Set the special tokens, registered when building the `Tokenizer`
+    pub fn with_special_tokens(mut self, tokens: Vec<AddedToken>) -> Self {
+        self.special_tokens = tokens;
+        self
+    }
+
+    /// Set the added tokens, registered when building the `Tokenizer`
+    pub fn with_added_tokens(mut self, tokens: Vec<AddedToken>) -> Self {
+        self.added_tokens = tokens;
+        self
+    }
+
+    /// Build the `Tokenizer`, registering the special tokens first, and then the added tokens
+    pub fn build(self) -> Tokenizer {
+        let mut tokenizer = self.tokenizer;
+        tokenizer.add_special_tokens(&self.special_tokens);
+        tokenizer.add_tokens(&self.added_tokens);
+        // The padding is checked against the vocabulary, so we set it once everything is there
+        tokenizer.with_padding(self.padding);
+        tokenizer
+    }
+}
+
 impl std::str::FromStr for Tokenizer {
     type Err = Error;
 
@@ -220,12 +575,18 @@ impl Tokenizer {
             decoder: None,
 
             added_vocabulary: AddedVocabulary::new(),
+            added_vocabulary_frozen: false,
 
             truncation: None,
             padding: None,
         }
     }
 
+    /// Get a `TokenizerBuilder` to build a `Tokenizer` with the given Model
+    pub fn builder(model: Box<dyn Model>) -> TokenizerBuilder {
+        TokenizerBuilder::new(model)
+    }
+
     /// Instantiate a new Tokenizer from the given file
     pub fn from_file<P: AsRef<Path>>(file: P) -> Result<Self> {
         let file = File::open(file)?;
@@ -252,6 +613,23 @@ impl Tokenizer {
         Ok(())
     }
 
+    /// Save the current tokenizer in the given folder, as `tokenizer.json`, along with the files
+    /// of its model. When a `name` is given, it prefixes all the file names, to avoid collisions
+    /// with other tokenizers saved in the same folder. Returns the list of written files.
+    pub fn save_pretrained(&self, folder: &Path, name: Option<&str>) -> Result<Vec<PathBuf>> {
+        let file_name = match name {
+            Some(name) => format!("{}-tokenizer.json", name),
+            None => "tokenizer.json".to_string(),
+        };
+        let path = folder.join(file_name);
+        let mut file = File::create(&path)?;
+        file.write_all(self.to_string(false)?.as_bytes())?;
+
+        let mut files = vec![path];
+        files.extend(self.model.save(folder, name)?);
+        Ok(files)
+    }
+
     /// Set the normalizer
     pub fn with_normalizer(&mut self, normalizer: Box<dyn Normalizer>) -> &Self {
         self.normalizer = Some(normalizer);
@@ -264,6 +642,11 @@ impl Tokenizer {
         self.normalizer.as_ref()
     }
 
+    /// Get the normalizer as its concrete type `N`, if it is one
+    pub fn normalizer_as<N: Normalizer>(&self) -> Option<&N> {
+        self.normalizer.as_ref()?.downcast_ref::<N>()
+    }
+
     /// Set the pre tokenizer
     pub fn with_pre_tokenizer(&mut self, pre_tokenizer: Box<dyn PreTokenizer>) -> &Self {
         self.pre_tokenizer = Some(pre_tokenizer);
@@ -276,6 +659,11 @@ impl Tokenizer {
         self.pre_tokenizer.as_ref()
     }
 
+    /// Get the pre tokenizer as its concrete type `PT`, if it is one
+    pub fn pre_tokenizer_as<PT: PreTokenizer>(&self) -> Option<&PT> {
+        self.pre_tokenizer.as_ref()?.downcast_ref::<PT>()
+    }
+
     /// Set the post processor
     pub fn with_post_processor(&mut self, post_processor: Box<dyn PostProcessor>) -> &Self {
         self.post_processor = Some(post_processor);
@@ -288,6 +676,11 @@ impl Tokenizer {
         self.post_processor.as_ref()
     }
 
+    /// Get the post processor as its concrete type `PP`, if it is one
+    pub fn post_processor_as<PP: PostProcessor>(&self) -> Option<&PP> {
+        self.post_processor.as_ref()?.downcast_ref::<PP>()
+    }
+
     /// Set the decoder
     pub fn with_decoder(&mut self, decoder: Box<dyn Decoder>) -> &Self {
         self.decoder = Some(decoder);
@@ -312,6 +705,11 @@ impl Tokenizer {
         &self.model
     }
 
+    /// Get the model as its concrete type `M`, if it is one
+    pub fn model_as<M: Model>(&self) -> Option<&M> {
+        self.model.downcast_ref::<M>()
+    }
+
     /// Set the truncation parameters
     pub fn with_truncation(&mut self, trunc: Option<TruncationParams>) -> &Self {
         self.truncation = trunc;
@@ -330,10 +728,27 @@ impl Tokenizer {
 
     /// Set the padding parameters
     pub fn with_padding(&mut self, padding: Option<PaddingParams>) -> &Self {
+        if let Some(Err(e)) = padding.as_ref().map(|p| self.check_padding(p)) {
+            println!("Warning: {}", e);
+        }
         self.padding = padding;
         self
     }
 
+    /// Check that the padding token has the padding id in our vocabulary
+    fn check_padding(&self, params: &PaddingParams) -> Result<()> {
+        let vocab_id = self.token_to_id(&params.pad_token);
+        if vocab_id == Some(params.pad_id) {
+            Ok(())
+        } else {
+            Err(Box::new(PaddingError::MismatchedPadToken {
+                pad_token: params.pad_token.clone(),
+                pad_id: params.pad_id,
+                vocab_id,
+            }))
+        }
+    }
+
     /// Get the currently set padding parameters
     pub fn get_padding(&self) -> Option<&PaddingParams> {
         self.padding.as_ref()
@@ -365,7 +780,12 @@ impl Tokenizer {
     pub fn get_vocab_size(&self, with_added_tokens: bool) -> usize {
         self.model.get_vocab_size()
             + if with_added_tokens {
-                self.added_vocabulary.len()
+                // Added tokens sharing their id with the model are counted only once
+                self.added_vocabulary
+                    .get_vocab()
+                    .values()
+                    .filter(|id| self.model.id_to_token(**id).is_none())
+                    .count()
             } else {
                 0
             }
@@ -382,9 +802,45 @@ impl Tokenizer {
         self.added_vocabulary.id_to_token(id, self.model.as_ref())
     }
 
+    /// Find all the tokens of the vocabulary, including the added tokens, that don't get back
+    /// to themselves through `token_to_id` and `id_to_token`, like two tokens sharing the
+    /// same id. They are sorted by id.
+    pub fn check_vocab_roundtrip(&self) -> Vec<(String, u32)> {
+        let mut inconsistent = self
+            .get_vocab(true)
+            .into_iter()
+            .filter(|(token, id)| {
+                self.token_to_id(token) != Some(*id)
+                    || self.id_to_token(*id) != Some(token.as_str())
+            })
+            .collect::<Vec<_>>();
+        inconsistent.sort_by(|(ta, ida), (tb, idb)| ida.cmp(idb).then(ta.cmp(tb)));
+        inconsistent
+    }
+
+    /// Get all the special tokens, in the order they were added
+    pub fn get_special_tokens(&self) -> Vec<&str> {
+        self.added_vocabulary.get_special_tokens()
+    }
+
+    /// Get the ids of all the special tokens, in the same order as `get_special_tokens`
+    pub fn get_special_tokens_ids(&self) -> Vec<u32> {
+        self.get_special_tokens()
+            .into_iter()
+            .filter_map(|token| self.token_to_id(token))
+            .collect()
+    }
+
+    /// Converts each of the given ids to the corresponding token.
+    pub fn ids_to_tokens(&self, ids: &[u32]) -> Vec<Option<String>> {
+        ids.iter()
+            .map(|id| self.id_to_token(*id).map(|t| t.to_owned()))
+            .collect()
+    }
+
     /// Normalize the given sentence and return the corresponding normalized string
     pub fn normalize(&self, sentence: &str) -> Result<NormalizedString> {
-        let mut normalized = self
+        let pieces = self
             .added_vocabulary
             .extract_and_normalize(self.normalizer.as_deref(), sentence)
             .into_iter()
@@ -398,88 +854,124 @@ impl Tokenizer {
             })
             .collect::<Result<Vec<_>>>()?;
 
-        let others = normalized.split_off(1);
-        let mut normalized: NormalizedString = normalized.into_iter().next().unwrap();
-        for n in others {
-            normalized.merge_with(&n);
-        }
-
-        Ok(normalized)
+        Ok(merge_normalized(pieces))
     }
 
-    /// Encode a single sequence
-    fn encode_single_sequence(&self, sequence: InputSequence, type_id: u32) -> Result<Encoding> {
-        let (sequence, pre_tokenized) = match sequence {
-            InputSequence::PreTokenized(seq) => (seq, true),
-            InputSequence::Raw(seq) => (vec![seq], false),
+    /// Run the pipeline shared by all the ways of encoding a sequence: the normalization, the
+    /// extraction of the added tokens, the pre-tokenization and the model.
+    fn tokenize_sequence(
+        &self,
+        sequence: InputSequence,
+        options: EncodeOptions,
+    ) -> Result<TokenizedSequence> {
+        // The offsets of the tokens are relative to each piece, unless we know where each
+        // piece starts in the original string
+        let (sequence, pre_tokenized, starts) = match sequence {
+            InputSequence::PreTokenized(seq) => (seq, true, vec![]),
+            InputSequence::Raw(seq) => (vec![seq], false, vec![]),
+            InputSequence::PreTokenizedWithOffsets(original, offsets) => {
+                let chars = original.chars().collect::<Vec<_>>();
+                if let Some((word, offsets)) = find_invalid_offsets(&offsets, chars.len()) {
+                    return Err(Box::new(OffsetsError::Input { word, offsets }));
+                }
+                let seq = offsets
+                    .iter()
+                    .map(|(start, end)| chars[*start..*end].iter().collect())
+                    .collect();
+                let starts = offsets.iter().map(|(start, _)| *start).collect();
+                (seq, true, starts)
+            }
         };
 
-        let mut sequence_encodings = vec![];
-        for subseq in sequence {
-            let results = self
+        let mut tokenized = TokenizedSequence::default();
+        for (i, subseq) in sequence.iter().enumerate() {
+            // Only the first piece of a pre-tokenized sequence may get a prefix space
+            let continuation = pre_tokenized && i > 0;
+            let mut offset = starts.get(i).copied().unwrap_or(0);
+            let mut normalized_offset = 0;
+            for (mut normalized, id) in self
                 .added_vocabulary
-                .extract_and_normalize(self.normalizer.as_deref(), &subseq)
-                .into_iter()
-                .map(
-                    |(mut normalized, id)| -> Result<(Encoding, NormalizedString)> {
-                        if let Some(id) = id {
-                            Ok((
-                                Encoding::new(
-                                    vec![id],
-                                    vec![type_id],
-                                    vec![normalized.get().to_owned()],
-                                    vec![Some(0)],
-                                    vec![(0, normalized.len())],
-                                    vec![0],
-                                    vec![1],
-                                    vec![],
-                                ),
-                                normalized,
-                            ))
-                        } else {
-                            // 1. Pre tokenization
-                            let pre_tokenized = self.pre_tokenize(&mut normalized)?;
-                            // 2. Model
-                            let tokens = self.model.tokenize(pre_tokenized)?;
-                            let encoding = Encoding::from_tokens(tokens, type_id);
-
-                            Ok((encoding, normalized))
-                        }
-                    },
-                );
-
-            let (all_encodings, all_normalized) =
-                ResultShunt::process(results, |iter| iter.unzip::<_, _, Vec<_>, Vec<_>>())?;
-            if all_encodings.is_empty() {
-                return Ok(Encoding::default());
-            }
-
-            let mut final_encoding = Encoding::default();
+                .extract_and_normalize(self.normalizer.as_deref(), subseq)
+            {
+                // The words are numbered across the whole sequence
+                let starting_word = tokenized.tokens.last().map_or(0, |t| t.word as usize + 1);
+                let mut tokens = if let Some(id) = id {
+                    check_words_count(starting_word + 1)?;
+                    vec![Token::new(
+                        id,
+                        normalized.get().to_owned(),
+                        (0, normalized.len()),
+                        starting_word as u32,
+                    )]
+                } else {
+                    // 1. Pre tokenization
+                    let pre_tokenized = if options.skip_pretokenize {
+                        vec![(normalized.get().to_owned(), (0, normalized.len()))]
+                    } else if options.split_lines {
+                        self.pre_tokenize_lines(&mut normalized, continuation)?
+                    } else if continuation {
+                        self.pre_tokenize_continuation(&mut normalized)?
+                    } else {
+                        self.pre_tokenize(&mut normalized)?
+                    };
+                    #[cfg(all(debug_assertions, feature = "validate-offsets"))]
+                    self.validate_pre_tokenized(&pre_tokenized, &normalized)?;
+                    check_words_count(starting_word + pre_tokenized.len())?;
+                    // 2. Model
+                    let words = pre_tokenized
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, (word, offsets))| (word, offsets, (starting_word + i) as u32))
+                        .collect();
+                    self.model.tokenize_words(words)?
+                };
 
-            let mut offset = 0; //final_normalized.len_original();
-            for (mut encoding, normalized) in all_encodings.into_iter().zip(all_normalized) {
-                encoding
-                    .get_offsets_mut()
-                    .iter_mut()
-                    .for_each(|(start, end)| {
-                        // We convert offsets back to original before merging
-                        let (s, e) = normalized
-                            .convert_offsets(Range::Normalized(*start..*end))
-                            .map_or((*start, *end), |range| (range.start, range.end));
-                        *start = s + offset;
-                        *end = e + offset;
-                    });
-                // We use the original length because we are merging offsets back to the
+                for token in &mut tokens {
+                    let (start, end) = token.offsets;
+                    if options.keep_normalized_offsets {
+                        tokenized
+                            .normalized_offsets
+                            .push((start + normalized_offset, end + normalized_offset));
+                    }
+                    // We convert offsets back to the original string
+                    let (start, end) = normalized
+                        .convert_offsets(Range::Normalized(start..end))
+                        .map_or((start, end), |range| (range.start, range.end));
+                    token.offsets = (start + offset, end + offset);
+                }
+                // We use the original length because the offsets are relative to the
                 // original referential
                 offset += normalized.len_original();
+                normalized_offset += normalized.len();
 
-                final_encoding.merge_with(encoding, false);
+                tokenized.tokens.extend(tokens);
+                tokenized.normalized.push(normalized);
             }
+        }
+
+        Ok(tokenized)
+    }
 
-            sequence_encodings.push(final_encoding);
+    /// Encode a single sequence, also returning the `NormalizedString` of each of its pieces
+    fn encode_single_sequence(
+        &self,
+        sequence: InputSequence,
+        type_id: u32,
+        options: EncodeOptions,
+    ) -> Result<(Encoding, Vec<NormalizedString>)> {
+        let tokenized = self.tokenize_sequence(sequence, options)?;
+        let unk_mask = if options.keep_unk_mask {
+            tokenized.tokens.iter().map(|token| token.unk).collect()
+        } else {
+            vec![]
+        };
+        let mut encoding = Encoding::from_tokens(tokenized.tokens, type_id);
+        if options.keep_normalized_offsets {
+            encoding.set_normalized_offsets(tokenized.normalized_offsets);
         }
+        encoding.set_unk_mask(unk_mask);
 
-        Ok(Encoding::merge(&sequence_encodings, !pre_tokenized))
+        Ok((encoding, tokenized.normalized))
     }
 
     /// Encode the given input. This method accepts both single sequences, as well as pair
@@ -509,103 +1001,618 @@ impl Tokenizer {
         input: E,
         add_special_tokens: bool,
     ) -> Result<Encoding> {
+        self.encode_with_options(
+            input,
+            EncodeOptions {
+                add_special_tokens,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Encode the given input, just like `encode`, but using the provided `EncodeOptions`.
+    pub fn encode_with_options<E: Into<EncodeInput>>(
+        &self,
+        input: E,
+        options: EncodeOptions,
+    ) -> Result<Encoding> {
+        Ok(self.encode_with_pieces(input.into(), options)?.0)
+    }
+
+    /// Encode the given input, just like `encode_with_options`, and also return the
+    /// `NormalizedString` of each piece of its first sequence
+    fn encode_with_pieces(
+        &self,
+        input: EncodeInput,
+        options: EncodeOptions,
+    ) -> Result<(Encoding, Vec<NormalizedString>)> {
         // Extract sequences from the EncodeInput
-        let (sequence, pair) = match input.into() {
+        let (sequence, pair) = match input {
             EncodeInput::Single(s1) => (s1, None),
             EncodeInput::Dual(s1, s2) => (s1, Some(s2)),
         };
+        // Overflowing windows avoid cutting the words of pre-tokenized sequences
+        let at_words = (
+            sequence.is_pre_tokenized(),
+            pair.as_ref().map_or(false, InputSequence::is_pre_tokenized),
+        );
+
+        #[cfg(all(debug_assertions, feature = "validate-offsets"))]
+        let originals = [Some(&sequence), pair.as_ref()]
+            .iter()
+            .map(|sequence| match sequence {
+                Some(InputSequence::Raw(s))
+                | Some(InputSequence::PreTokenizedWithOffsets(s, _)) => Some(s.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
 
         // Encode each sequence
-        let encoding = self.encode_single_sequence(sequence, 0)?;
+        let (encoding, pieces) = self.encode_single_sequence(sequence, 0, options)?;
         let pair_encoding = match pair {
-            Some(sequence) => Some(self.encode_single_sequence(sequence, 1)?),
+            Some(sequence) => Some(self.encode_single_sequence(sequence, 1, options)?.0),
             None => None,
         };
 
+        // The offsets of pre-tokenized sequences are relative to each word, so we can only
+        // check the raw ones, and the ones given with the offsets of their words
+        #[cfg(all(debug_assertions, feature = "validate-offsets"))]
+        for (encoding, original) in std::iter::once(&encoding)
+            .chain(pair_encoding.as_ref())
+            .zip(originals)
+        {
+            if let Some(original) = original {
+                if let Err(e) = encoding.validate_offsets(&original) {
+                    // The content of a token may legitimately differ from the original string,
+                    // like with the bytes of `ByteLevel` or the accents stripped by a normalizer
+                    match e.downcast_ref::<OffsetsError>() {
+                        Some(OffsetsError::Mismatch { .. }) => println!("Warning: {}", e),
+                        _ => return Err(e),
+                    }
+                }
+            }
+        }
+
+        if options.error_on_overflow {
+            if let Some(trunc) = &self.truncation {
+                let n_added_tokens = match &self.post_processor {
+                    Some(processor) if options.add_special_tokens => {
+                        processor.added_tokens(pair_encoding.is_some())
+                    }
+                    _ => 0,
+                };
+                let max_length = trunc.max_length.saturating_sub(n_added_tokens);
+                let length = encoding.len() + pair_encoding.as_ref().map_or(0, |e| e.len());
+                if length > max_length {
+                    return Err(Box::new(OverflowError { length, max_length }));
+                }
+            }
+        }
+
         // And finally post process
-        self.post_process(encoding, pair_encoding, add_special_tokens)
+        let mut encoding = self.do_post_process(
+            encoding,
+            pair_encoding,
+            options.add_special_tokens,
+            at_words,
+        )?;
+
+        if options.reject_nul_bytes {
+            for encoding in std::iter::once(&encoding).chain(encoding.get_overflowing()) {
+                if let Some((token, value)) = encoding
+                    .get_tokens()
+                    .iter()
+                    .enumerate()
+                    .find(|(_, value)| value.contains('\0'))
+                {
+                    return Err(Box::new(NulByteError {
+                        token,
+                        value: value.to_owned(),
+                    }));
+                }
+            }
+        }
+
+        Ok((encoding, pieces))
     }
 
-    /// Encode all the sentences in parallel, using multiple threads
-    pub fn encode_batch<E: Into<EncodeInput> + Send>(
+    /// Encode `prefix + suffix`, reusing `prefix_encoding`, the encoding of `prefix` obtained
+    /// with `encode(prefix, false)`. This avoids processing the same prefix again and again,
+    /// like a long system prompt shared by many inputs.
+    ///
+    /// Only the last word of the prefix is encoded again, along with the suffix, so the prefix
+    /// must end on a clean pre-token boundary: the tokenization of the suffix must not depend on
+    /// anything before this last word. We check that this last word is tokenized just like in
+    /// `prefix_encoding`, and return a `PrefixError` otherwise, in which case `encode` should be
+    /// used with the whole input. The prefix can't be pre-tokenized, and the truncation applies
+    /// to the whole input, just like with `encode`.
+    pub fn encode_with_prefix(
         &self,
-        inputs: Vec<E>,
+        prefix: &str,
+        prefix_encoding: &Encoding,
+        suffix: &str,
         add_special_tokens: bool,
-    ) -> Result<Vec<Encoding>> {
-        let mut encodings = inputs
-            .into_maybe_par_iter()
-            .map(|input| self.encode(input, add_special_tokens))
-            .collect::<Result<Vec<Encoding>>>()?;
+    ) -> Result<Encoding> {
+        if prefix_encoding
+            .get_special_tokens_mask()
+            .iter()
+            .any(|mask| *mask == 1)
+        {
+            return Err(Box::new(PrefixError::SpecialTokens));
+        }
 
-        if let Some(params) = &self.padding {
-            // We do the padding here to make sure we handle the batch padding
-            pad_encodings(&mut encodings, &params)?;
+        // Find where the last word of the prefix starts
+        let words = prefix_encoding.get_words();
+        let cut = words
+            .last()
+            .and_then(|last| words.iter().position(|word| word == last))
+            .unwrap_or(0);
+        let start = prefix_encoding.get_offsets().get(cut).map_or(0, |o| o.0);
+
+        let tail = prefix
+            .chars()
+            .skip(start)
+            .chain(suffix.chars())
+            .collect::<String>();
+        let (mut encoding, _) =
+            self.encode_single_sequence(tail.into(), 0, EncodeOptions::default())?;
+        let last_word = &prefix_encoding.get_ids()[cut..];
+        if !encoding.get_ids().starts_with(last_word) {
+            return Err(Box::new(PrefixError::NotOnBoundary));
         }
+        encoding.get_offsets_mut().iter_mut().for_each(|(s, e)| {
+            *s += start;
+            *e += start;
+        });
+
+        let mut final_encoding = prefix_encoding.slice(0..cut);
+        final_encoding.merge_with(encoding, false);
+        self.do_post_process(final_encoding, None, add_special_tokens, (false, false))
+    }
 
-        Ok(encodings)
+    /// Encode a single sequence, just like `encode`, but using the given `type_id` for its
+    /// tokens instead of 0. This helps assembling inputs made of more than two segments. The
+    /// special tokens added by the `PostProcessor` keep their usual type id.
+    pub fn encode_with_type_id<S: Into<InputSequence>>(
+        &self,
+        sequence: S,
+        type_id: u32,
+        add_special_tokens: bool,
+    ) -> Result<Encoding> {
+        let sequence = sequence.into();
+        let at_words = (sequence.is_pre_tokenized(), false);
+
+        let (encoding, _) =
+            self.encode_single_sequence(sequence, type_id, EncodeOptions::default())?;
+        self.do_post_process(encoding, None, add_special_tokens, at_words)
     }
 
-    /// Decode the given ids, back to a String
-    pub fn decode(&self, ids: Vec<u32>, skip_special_tokens: bool) -> Result<String> {
-        let tokens = ids
-            .into_iter()
-            .map(|id| {
-                self.added_vocabulary
-                    .id_to_token(id, self.model.as_ref())
-                    .filter(|token| {
-                        !skip_special_tokens || !self.added_vocabulary.is_special_token(token)
-                    })
-                    .map(|t| t.to_owned())
-            })
-            .filter(|token| token.is_some())
-            .map(|id| id.unwrap())
-            .collect::<Vec<_>>();
+    /// Count the tokens that `encode` would produce for the given input, including the special
+    /// tokens added by the `PostProcessor` when `add_special_tokens` is set. This runs the
+    /// whole pipeline but skips building the `Encoding`, which makes it faster when only the
+    /// count matters. Neither the truncation nor the padding are applied.
+    pub fn count_tokens<E: Into<EncodeInput>>(
+        &self,
+        input: E,
+        add_special_tokens: bool,
+    ) -> Result<usize> {
+        let (sequence, pair) = match input.into() {
+            EncodeInput::Single(s1) => (s1, None),
+            EncodeInput::Dual(s1, s2) => (s1, Some(s2)),
+        };
+        let is_pair = pair.is_some();
 
-        if let Some(decoder) = &self.decoder {
-            decoder.decode(tokens)
-        } else {
-            Ok(tokens.join(" "))
+        let mut count = self.count_sequence_tokens(sequence)?;
+        if let Some(pair) = pair {
+            count += self.count_sequence_tokens(pair)?;
+        }
+        if add_special_tokens {
+            count += self
+                .post_processor
+                .as_ref()
+                .map_or(0, |processor| processor.added_tokens(is_pair));
         }
+
+        Ok(count)
     }
 
-    /// Decode all sentences in parallel
-    pub fn decode_batch(
+    /// Count the tokens of a single sequence, going through the same pipeline as `encode`
+    fn count_sequence_tokens(&self, sequence: InputSequence) -> Result<usize> {
+        Ok(self
+            .tokenize_sequence(sequence, EncodeOptions::default())?
+            .tokens
+            .len())
+    }
+
+    /// Encode the given input just like `encode`, but only return the ids. The truncation, the
+    /// special tokens and the padding still apply, but the tokens and their offsets are dropped
+    /// right after the model, which saves both time and allocations when only the ids matter.
+    pub fn encode_ids<E: Into<EncodeInput>>(
         &self,
-        sentences: Vec<Vec<u32>>,
-        skip_special_tokens: bool,
-    ) -> Result<Vec<String>> {
-        sentences
-            .into_maybe_par_iter()
-            .map(|sentence| self.decode(sentence, skip_special_tokens))
-            .collect()
+        input: E,
+        add_special_tokens: bool,
+    ) -> Result<Vec<u32>> {
+        let (sequence, pair) = match input.into() {
+            EncodeInput::Single(s1) => (s1, None),
+            EncodeInput::Dual(s1, s2) => (s1, Some(s2)),
+        };
+        let at_words = (
+            sequence.is_pre_tokenized(),
+            pair.as_ref().map_or(false, InputSequence::is_pre_tokenized),
+        );
+
+        let encoding = self.encode_sequence_ids(sequence, 0)?;
+        let pair_encoding = match pair {
+            Some(sequence) => Some(self.encode_sequence_ids(sequence, 1)?),
+            None => None,
+        };
+
+        Ok(self
+            .do_post_process(encoding, pair_encoding, add_special_tokens, at_words)?
+            .into_ids())
     }
 
-    /// Train a model and replace our current Model, using the given Trainer
-    #[allow(clippy::borrowed_box)]
-    fn word_count(
-        &mut self,
-        trainer: &Box<dyn Trainer>,
+    /// Encode a single sequence for `encode_ids`. The resulting `Encoding` only has its ids,
+    /// type ids and words set, the tokens are empty and the offsets all `(0, 0)`.
+    fn encode_sequence_ids(&self, sequence: InputSequence, type_id: u32) -> Result<Encoding> {
+        let (ids, words): (Vec<_>, Vec<_>) = self
+            .tokenize_sequence(sequence, EncodeOptions::default())?
+            .tokens
+            .into_iter()
+            .map(|token| (token.id, Some(token.word)))
+            .unzip();
+
+        let len = ids.len();
+        Ok(Encoding::new(
+            ids,
+            vec![type_id; len],
+            vec![String::new(); len],
+            words,
+            vec![(0, 0); len],
+            vec![0; len],
+            vec![1; len],
+            vec![],
+        ))
+    }
+
+    /// Run the normalization, pre-tokenization and model steps on the given sequence, and
+    /// return the resulting tokens without building an `Encoding`. Just like in an `Encoding`,
+    /// the offsets are relative to the original string (to each of its pieces when
+    /// pre-tokenized), and the words are numbered across the whole sequence.
+    pub fn tokenize<S: Into<InputSequence>>(&self, sequence: S) -> Result<Vec<Token>> {
+        Ok(self
+            .tokenize_sequence(sequence.into(), EncodeOptions::default())?
+            .tokens)
+    }
+
+    /// Encode the given bytes, handling any invalid UTF-8 sequence as specified by `invalid`,
+    /// instead of always replacing it with `U+FFFD`. The offsets are in chars of the converted
+    /// string, just like with `encode`.
+    pub fn encode_bytes(
+        &self,
+        bytes: &[u8],
+        invalid: InvalidUtf8,
+        add_special_tokens: bool,
+    ) -> Result<Encoding> {
+        let sequence = from_utf8_lossy_with(bytes, invalid).into_owned();
+        self.encode(sequence, add_special_tokens)
+    }
+
+    /// Encode an input that has already been normalized. The normalizer doesn't run again, and
+    /// the added tokens are not extracted, so they won't be split out of the input. The offsets
+    /// are relative to the original string of the given `NormalizedString`.
+    pub fn encode_normalized(
+        &self,
+        mut normalized: NormalizedString,
+        add_special_tokens: bool,
+    ) -> Result<Encoding> {
+        let pre_tokenized = self.pre_tokenize(&mut normalized)?;
+        check_words_count(pre_tokenized.len())?;
+        let mut encoding = Encoding::from_tokens(self.model.tokenize(pre_tokenized)?, 0);
+        encoding
+            .get_offsets_mut()
+            .iter_mut()
+            .for_each(|(start, end)| {
+                if let Some(range) = normalized.convert_offsets(Range::Normalized(*start..*end)) {
+                    *start = range.start;
+                    *end = range.end;
+                }
+            });
+
+        self.post_process(encoding, None, add_special_tokens)
+    }
+
+    /// Encode all the sentences in parallel, using multiple threads
+    pub fn encode_batch<E: Into<EncodeInput> + Send>(
+        &self,
+        inputs: Vec<E>,
+        add_special_tokens: bool,
+    ) -> Result<Vec<Encoding>> {
+        self.encode_batch_with_progress(inputs, add_special_tokens, usize::MAX, |_| {})
+    }
+
+    /// Encode all the sentences in parallel, just like `encode_batch`, but encoding each distinct
+    /// input only once. Duplicates get a clone of the first `Encoding`, which is only worth it
+    /// when there are many of them, or when inputs are long enough.
+    pub fn encode_batch_dedup<E: Into<EncodeInput> + Send>(
+        &self,
+        inputs: Vec<E>,
+        add_special_tokens: bool,
+    ) -> Result<Vec<Encoding>> {
+        let mut unique = vec![];
+        let mut seen = HashMap::new();
+        let indices = inputs
+            .into_iter()
+            .map(|input| {
+                let input: EncodeInput = input.into();
+                *seen.entry(input.clone()).or_insert_with(|| {
+                    unique.push(input);
+                    unique.len() - 1
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // Padding only depends on the distinct lengths, so it can happen before cloning
+        let encodings = self.encode_batch(unique, add_special_tokens)?;
+        Ok(indices.into_iter().map(|i| encodings[i].clone()).collect())
+    }
+
+    /// Encode each line of the given reader, keeping its line ending, without reading it all in
+    /// memory. The lines are encoded in parallel by chunks, each one just like with `encode`, so
+    /// the padding applies to each line on its own. There is exactly one result per line, with
+    /// any reading or encoding error in place of the line where it happened.
+    pub fn encode_file<'s, R: BufRead + 's>(
+        &'s self,
+        reader: R,
+        add_special_tokens: bool,
+    ) -> impl Iterator<Item = Result<Encoding>> + 's {
+        const CHUNK_SIZE: usize = 1_000;
+
+        let mut lines = reader.lines_with_ending();
+        std::iter::from_fn(move || {
+            let chunk = lines.by_ref().take(CHUNK_SIZE).collect::<Vec<_>>();
+            if chunk.is_empty() {
+                return None;
+            }
+
+            Some(
+                chunk
+                    .into_maybe_par_iter()
+                    .map(|line| -> Result<Encoding> { self.encode(line?, add_special_tokens) })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .flatten()
+    }
+
+    /// Encode all the sentences in parallel, just like `encode_batch`, and report the progress
+    /// by calling `progress` with the number of inputs encoded so far. This happens every
+    /// `report_every` inputs, and once at the end.
+    pub fn encode_batch_with_progress<E, F>(
+        &self,
+        inputs: Vec<E>,
+        add_special_tokens: bool,
+        report_every: usize,
+        progress: F,
+    ) -> Result<Vec<Encoding>>
+    where
+        E: Into<EncodeInput> + Send,
+        F: Fn(usize) + Sync,
+    {
+        let report_every = report_every.max(1);
+        let encoded = AtomicUsize::new(0);
+        let mut encodings = inputs
+            .into_maybe_par_iter()
+            .map(|input| {
+                let encoding = self.encode(input, add_special_tokens);
+                let done = encoded.fetch_add(1, Ordering::Relaxed) + 1;
+                if done % report_every == 0 {
+                    progress(done);
+                }
+                encoding
+            })
+            .collect::<Result<Vec<Encoding>>>()?;
+        if encodings.len() % report_every != 0 {
+            progress(encodings.len());
+        }
+
+        if let Some(params) = &self.padding {
+            if params.strict {
+                self.check_padding(params)?;
+            }
+            // We do the padding here to make sure we handle the batch padding
+            pad_encodings(&mut encodings, &params)?;
+        }
+
+        Ok(encodings)
+    }
+
+    /// Encode the given sequence, just like `encode`, and also return the `NormalizedString` it
+    /// went through, to check how the normalization aligns with the original string.
+    pub fn encode_with_normalized(
+        &self,
+        sequence: &str,
+        add_special_tokens: bool,
+    ) -> Result<(Encoding, NormalizedString)> {
+        let options = EncodeOptions {
+            add_special_tokens,
+            ..Default::default()
+        };
+        let (encoding, pieces) = self.encode_with_pieces(sequence.into(), options)?;
+        Ok((encoding, merge_normalized(pieces)))
+    }
+
+    /// Encode all the sequences in parallel, just like `encode_batch`, and return each
+    /// `Encoding` along with its `NormalizedString`. Only single sequences are supported.
+    /// Keeping all the normalized strings has a memory cost, so prefer `encode_batch` when they
+    /// are not needed.
+    pub fn encode_batch_with_normalized<S: AsRef<str> + Send>(
+        &self,
+        inputs: Vec<S>,
+        add_special_tokens: bool,
+    ) -> Result<Vec<(Encoding, NormalizedString)>> {
+        let (mut encodings, normalized): (Vec<_>, Vec<_>) = inputs
+            .into_maybe_par_iter()
+            .map(|input| self.encode_with_normalized(input.as_ref(), add_special_tokens))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .unzip();
+
+        if let Some(params) = &self.padding {
+            if params.strict {
+                self.check_padding(params)?;
+            }
+            pad_encodings(&mut encodings, &params)?;
+        }
+
+        Ok(encodings.into_iter().zip(normalized).collect())
+    }
+
+    /// Decode the given ids, back to a String
+    pub fn decode(&self, ids: Vec<u32>, skip_special_tokens: bool) -> Result<String> {
+        self.decode_with_options(
+            ids,
+            &DecodeOptions {
+                skip_special_tokens,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Decode the given ids, back to a String, using the given `DecodeOptions`
+    pub fn decode_with_options(&self, ids: Vec<u32>, options: &DecodeOptions) -> Result<String> {
+        let tokens = ids.into_iter().filter_map(|id| {
+            self.added_vocabulary
+                .id_to_token(id, self.model.as_ref())
+                .map(|token| (id, token))
+        });
+        let skipped = |id: u32, token: &str| {
+            options.skip_ids.contains(&id)
+                || (options.skip_special_tokens && self.added_vocabulary.is_special_token(token))
+        };
+
+        let decoded = if let Some(decoder) = &self.decoder {
+            // We let the decoder know where some special tokens have been removed
+            let mut groups = vec![vec![]];
+            for (id, token) in tokens {
+                if skipped(id, token) {
+                    groups.push(vec![]);
+                } else {
+                    groups.last_mut().unwrap().push(token.to_owned());
+                }
+            }
+            groups.retain(|group| !group.is_empty());
+            decoder.decode_groups(groups)?
+        } else {
+            // Without a decoder, we can write directly in the output buffer
+            let mut decoded = String::new();
+            let tokens = tokens.filter(|(id, token)| !skipped(*id, *token));
+            for (i, (_, token)) in tokens.enumerate() {
+                if i > 0 {
+                    decoded.push(' ');
+                }
+                decoded.push_str(token);
+            }
+            decoded
+        };
+
+        if options.clean_up_tokenization_spaces {
+            Ok(crate::decoders::cleanup(&decoded))
+        } else {
+            Ok(decoded)
+        }
+    }
+
+    /// Reconstruct the parts of `original` covered by the given `Encoding`, by slicing it
+    /// with the offsets of each non-special token. This is useful to check the offsets
+    /// against the output of `decode`. Whatever is not covered by any token, like the
+    /// whitespaces between words, is not part of the result.
+    pub fn reconstruct(&self, encoding: &Encoding, original: &str) -> String {
+        let mut reconstructed = String::new();
+        let mut last_offsets = None;
+        for (offsets, special) in encoding
+            .get_offsets()
+            .iter()
+            .zip(encoding.get_special_tokens_mask())
+        {
+            // Some tokens share their offsets (a char split over multiple tokens for example)
+            if *special == 1 || last_offsets == Some(offsets) {
+                continue;
+            }
+            if let Some(piece) = get_range_of(original, offsets.0..offsets.1) {
+                reconstructed.push_str(piece);
+            }
+            last_offsets = Some(offsets);
+        }
+        reconstructed
+    }
+
+    /// Decode all sentences in parallel
+    pub fn decode_batch(
+        &self,
+        sentences: Vec<Vec<u32>>,
+        skip_special_tokens: bool,
+    ) -> Result<Vec<String>> {
+        sentences
+            .into_maybe_par_iter()
+            .map(|sentence| self.decode(sentence, skip_special_tokens))
+            .collect()
+    }
+
+    /// Decode all sentences in parallel, using the same `DecodeOptions` for each of them
+    pub fn decode_batch_with(
+        &self,
+        sentences: Vec<Vec<u32>>,
+        options: DecodeOptions,
+    ) -> Result<Vec<String>> {
+        sentences
+            .into_maybe_par_iter()
+            .map(|sentence| self.decode_with_options(sentence, &options))
+            .collect()
+    }
+
+    /// Count the words in the given files, reporting the progress if the Trainer asks for it
+    #[allow(clippy::borrowed_box)]
+    fn word_count(
+        &mut self,
+        trainer: &Box<dyn Trainer>,
         files: Vec<String>,
     ) -> Result<HashMap<String, u32>> {
-        let max_read = 1_000_000;
+        if !trainer.should_show_progress() {
+            return self.count_words(trainer, files, |_| {});
+        }
+
         let len: u64 = files
             .iter()
             .map(|filename| File::open(filename).unwrap().metadata().unwrap().len() as u64)
             .sum();
+        let progress = ProgressBar::new(len);
+        progress.set_style(trainer.progress_style().unwrap_or_else(|| {
+            ProgressStyle::default_bar()
+                .template("[{elapsed_precise}] {msg:<40!} {wide_bar} {percent:>19!}")
+        }));
+        progress.set_message(&format!("Reading files ({:.2} Mo)", len / 1_000_000));
+        progress.set_draw_delta(len / 100); // Redraw only every 2%
+
+        let words = self.count_words(trainer, files, |read| progress.inc(read))?;
+        progress.finish();
+        Ok(words)
+    }
 
-        let progress = if trainer.should_show_progress() {
-            let progress = ProgressBar::new(len);
-            progress.set_style(
-                ProgressStyle::default_bar()
-                    .template("[{elapsed_precise}] {msg:<40!} {wide_bar} {percent:>19!}"),
-            );
-            progress.set_message(&format!("Reading files ({:.2} Mo)", len / 1_000_000));
-            progress.set_draw_delta(len / 100); // Redraw only every 2%
-            Some(progress)
-        } else {
-            None
-        };
-        let words = files
+    /// Count the words in the given files, calling `on_read` with the number of bytes
+    /// processed after each line
+    #[allow(clippy::borrowed_box)]
+    fn count_words<F>(
+        &self,
+        trainer: &Box<dyn Trainer>,
+        files: Vec<String>,
+        on_read: F,
+    ) -> Result<HashMap<String, u32>>
+    where
+        F: Fn(u64) + Sync,
+    {
+        let max_read = 1_000_000;
+        files
             .into_iter()
             .map(|filename| -> Result<HashMap<String, u32>> {
                 let file = File::open(filename)?;
@@ -615,26 +1622,19 @@ impl Tokenizer {
                 // We use an iterator to be able to chain with par_bridge.
                 file.lines_with_ending()
                     .maybe_par_bridge()
-                    .map_with(
-                        &progress,
-                        |progress, line| -> Result<HashMap<String, u32>> {
-                            let newline = line?;
-                            let mut words = HashMap::new();
-                            let mut normalized =
-                                self.do_normalize(NormalizedString::from(&newline))?;
-                            let pre_tokenized = self.pre_tokenize(&mut normalized)?;
-                            trainer.process_tokens(
-                                &mut words,
-                                pre_tokenized.into_iter().map(|(t, _)| t).collect(),
-                            );
-
-                            let b = newline.len();
-                            if let Some(pbar) = progress {
-                                pbar.inc(b as u64);
-                            }
-                            Ok(words)
-                        },
-                    )
+                    .map(|line| -> Result<HashMap<String, u32>> {
+                        let newline = line?;
+                        let mut words = HashMap::new();
+                        let mut normalized = self.do_normalize(NormalizedString::from(&newline))?;
+                        let pre_tokenized = self.pre_tokenize(&mut normalized)?;
+                        trainer.process_tokens(
+                            &mut words,
+                            pre_tokenized.into_iter().map(|(t, _)| t).collect(),
+                        );
+
+                        on_read(newline.len() as u64);
+                        Ok(words)
+                    })
                     .reduce(
                         || Ok(HashMap::new()),
                         |acc, ws| {
@@ -654,19 +1654,26 @@ impl Tokenizer {
                     }
                     Ok(acc)
                 },
-            )?;
-        if let Some(pbar) = progress {
-            pbar.finish();
-        }
-        Ok(words)
+            )
     }
 
     /// Train a model and replace our current Model, using the given Trainer
     #[allow(clippy::borrowed_box)]
     pub fn train(&mut self, trainer: &Box<dyn Trainer>, files: Vec<String>) -> Result<()> {
         let words = self.word_count(trainer, files)?;
+        self.train_from_word_counts(trainer, words)
+    }
 
-        let (model, special_tokens) = trainer.train(words)?;
+    /// Train a model and replace our current Model, using the given Trainer and some word
+    /// counts computed beforehand (for example by a distributed job). This skips reading the
+    /// files, and directly goes to the training step.
+    #[allow(clippy::borrowed_box)]
+    pub fn train_from_word_counts(
+        &mut self,
+        trainer: &Box<dyn Trainer>,
+        word_counts: HashMap<String, u32>,
+    ) -> Result<()> {
+        let (model, special_tokens) = trainer.train(word_counts)?;
         self.model = model;
         self.add_special_tokens(&special_tokens);
 
@@ -674,14 +1681,94 @@ impl Tokenizer {
     }
 
     /// PreTokenization logic, handling the case where there is no PreTokenizer set
-    fn pre_tokenize(
+    fn pre_tokenize(&self, normalized: &mut NormalizedString) -> Result<Vec<(String, Offsets)>> {
+        let words = match &self.pre_tokenizer {
+            None => vec![(normalized.get().to_owned(), (0, normalized.len()))],
+            Some(pre_tokenizer) => pre_tokenizer.pre_tokenize(normalized)?,
+        };
+        Ok(words)
+    }
+
+    /// Same as `pre_tokenize`, for any piece but the first of a pre-tokenized sequence
+    fn pre_tokenize_continuation(
+        &self,
+        normalized: &mut NormalizedString,
+    ) -> Result<Vec<(String, Offsets)>> {
+        let words = match &self.pre_tokenizer {
+            None => vec![(normalized.get().to_owned(), (0, normalized.len()))],
+            Some(pre_tokenizer) => pre_tokenizer.pre_tokenize_continuation(normalized)?,
+        };
+        Ok(words)
+    }
+
+    /// Check that the words returned by the `PreTokenizer` are in bounds, and don't overlap
+    /// each other, which would break the offsets of the final `Encoding`
+    #[cfg(all(debug_assertions, feature = "validate-offsets"))]
+    fn validate_pre_tokenized(
+        &self,
+        pre_tokenized: &[(String, Offsets)],
+        normalized: &NormalizedString,
+    ) -> Result<()> {
+        let offsets = pre_tokenized.iter().map(|(_, offsets)| offsets);
+        if let Some((word, offsets)) = find_invalid_offsets(offsets, normalized.len()) {
+            // The name of the pre-tokenizer is the one used to serialize it
+            let pre_tokenizer = self
+                .pre_tokenizer
+                .as_ref()
+                .and_then(|pre_tokenizer| serde_json::to_value(pre_tokenizer).ok())
+                .and_then(|value| value["type"].as_str().map(|name| name.to_owned()))
+                .unwrap_or_else(|| "the PreTokenizer".into());
+            return Err(Box::new(OffsetsError::PreTokenized {
+                pre_tokenizer,
+                word,
+                offsets,
+            }));
+        }
+        Ok(())
+    }
+
+    /// Training reads the files line by line, keeping the line endings, and pre-tokenizes each
+    /// line on its own. This does the same for any input, for `EncodeOptions::split_lines`.
+    fn pre_tokenize_lines(
         &self,
-        mut normalized: &mut NormalizedString,
+        normalized: &mut NormalizedString,
+        continuation: bool,
     ) -> Result<Vec<(String, Offsets)>> {
-        match &self.pre_tokenizer {
-            None => Ok(vec![(normalized.get().to_owned(), (0, normalized.len()))]),
-            Some(pre_tokenizer) => pre_tokenizer.pre_tokenize(&mut normalized),
+        let pre_tokenize = |normalized: &mut NormalizedString| {
+            if continuation {
+                self.pre_tokenize_continuation(normalized)
+            } else {
+                self.pre_tokenize(normalized)
+            }
+        };
+
+        let lines = normalized.split('\n', SplitDelimiterBehavior::MergedWithPrevious);
+        // Without any PreTokenizer, the whole input stays a single word
+        if self.pre_tokenizer.is_none() || lines.len() < 2 {
+            return pre_tokenize(normalized);
+        }
+
+        let mut words = vec![];
+        let mut merged: Option<NormalizedString> = None;
+        for (mut line, _) in lines {
+            let offset = merged.as_ref().map_or(0, |merged| merged.len());
+            words.extend(
+                pre_tokenize(&mut line)?
+                    .into_iter()
+                    .map(|(word, (start, end))| (word, (start + offset, end + offset))),
+            );
+            if let Some(merged) = merged.as_mut() {
+                merged.merge_with(&line);
+            } else {
+                merged = Some(line);
+            }
+        }
+        // The lines may have been modified by the pre-tokenizer, so we keep them
+        if let Some(merged) = merged {
+            *normalized = merged;
         }
+
+        Ok(words)
     }
 
     /// Normalization logic, go through all normalizers
@@ -699,6 +1786,18 @@ impl Tokenizer {
         encoding: Encoding,
         pair_encoding: Option<Encoding>,
         add_special_tokens: bool,
+    ) -> Result<Encoding> {
+        self.do_post_process(encoding, pair_encoding, add_special_tokens, (false, false))
+    }
+
+    /// Post processing logic, with `at_words` telling for each sequence whether truncation
+    /// should avoid cutting words
+    fn do_post_process(
+        &self,
+        encoding: Encoding,
+        pair_encoding: Option<Encoding>,
+        add_special_tokens: bool,
+        at_words: (bool, bool),
     ) -> Result<Encoding> {
         // 1. First we truncate if needed
         let (encoding, pair_encoding) = {
@@ -714,9 +1813,9 @@ impl Tokenizer {
                         max_length: trunc.max_length - n_added_tokens,
                         ..*trunc
                     };
-                    truncate_encodings(encoding, pair_encoding, &params)?
+                    truncate_encodings_at_words(encoding, pair_encoding, &params, at_words)?
                 } else {
-                    truncate_encodings(encoding, pair_encoding, &trunc)?
+                    truncate_encodings_at_words(encoding, pair_encoding, &trunc, at_words)?
                 }
             } else {
                 (encoding, pair_encoding)
@@ -732,6 +1831,9 @@ impl Tokenizer {
 
         // 3. Then we pad if needed
         let [final_encoding] = if let Some(params) = &self.padding {
+            if params.strict {
+                self.check_padding(params)?;
+            }
             let mut arr = [final_encoding];
             pad_encodings(&mut arr, params)?;
             arr
@@ -745,6 +1847,9 @@ impl Tokenizer {
     /// Register the given tokens as special tokens. This is especially useful for removing
     /// these special tokens while decoding
     pub fn add_special_tokens(&mut self, tokens: &[AddedToken]) -> usize {
+        if self.check_frozen(tokens) {
+            return 0;
+        }
         self.added_vocabulary.add_special_tokens(
             tokens,
             self.model.as_ref(),
@@ -754,7 +1859,1321 @@ impl Tokenizer {
 
     /// Add the given tokens to the added vocabulary
     pub fn add_tokens(&mut self, tokens: &[AddedToken]) -> usize {
+        if self.check_frozen(tokens) {
+            return 0;
+        }
         self.added_vocabulary
             .add_tokens(tokens, self.model.as_ref(), self.normalizer.as_deref())
     }
+
+    /// Freeze the added vocabulary, so that any later call to `add_tokens` or
+    /// `add_special_tokens` adds nothing and prints a warning. This keeps the ids of a deployed
+    /// tokenizer from shifting. The frozen state is not serialized.
+    pub fn freeze_added_vocabulary(&mut self) {
+        self.added_vocabulary_frozen = true;
+    }
+
+    /// Whether the added vocabulary has been frozen with `freeze_added_vocabulary`
+    pub fn is_frozen(&self) -> bool {
+        self.added_vocabulary_frozen
+    }
+
+    /// Warn about the given tokens being ignored if the added vocabulary is frozen
+    fn check_frozen(&self, tokens: &[AddedToken]) -> bool {
+        if self.added_vocabulary_frozen && !tokens.is_empty() {
+            println!(
+                "Warning: The added vocabulary is frozen, ignoring {} token(s)",
+                tokens.len()
+            );
+        }
+        self.added_vocabulary_frozen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::bpe::{BpeTrainer, BPE};
+    use crate::models::wordpiece::WordPiece;
+    #[cfg(all(debug_assertions, feature = "validate-offsets"))]
+    use crate::normalizers::replace::Replace;
+    use crate::normalizers::strip::Strip;
+    use crate::normalizers::utils::Lowercase;
+    use crate::pre_tokenizers::byte_level::ByteLevel;
+    use crate::pre_tokenizers::delimiter::CharDelimiterSplit;
+    use crate::pre_tokenizers::metaspace::Metaspace;
+    use crate::processors::bert::BertProcessing;
+    use serde::{Deserialize, Serialize};
+
+    /// A pre-tokenizer returning overlapping words
+    #[cfg(all(debug_assertions, feature = "validate-offsets"))]
+    #[derive(Serialize, Deserialize)]
+    struct OverlappingPreTokenizer;
+
+    #[cfg(all(debug_assertions, feature = "validate-offsets"))]
+    #[typetag::serde]
+    impl PreTokenizer for OverlappingPreTokenizer {
+        fn pre_tokenize(&self, _: &mut NormalizedString) -> Result<Vec<(String, Offsets)>> {
+            Ok(vec![("ab".into(), (0, 2)), ("bc".into(), (1, 3))])
+        }
+    }
+
+    /// A vocabulary with the given tokens, each having its position as id
+    fn vocab_of(tokens: &[&str]) -> HashMap<String, u32> {
+        tokens
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (t.to_string(), i as u32))
+            .collect()
+    }
+
+    /// A `BPE` without any merge, with the given tokens as vocabulary
+    fn simple_bpe(tokens: &[&str]) -> BPE {
+        BPE::builder()
+            .vocab_and_merges(vocab_of(tokens), HashMap::new())
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn normalize_empty_input() {
+        let tokenizer = Tokenizer::new(Box::new(BPE::default()));
+        let normalized = tokenizer.normalize("").unwrap();
+        assert_eq!(normalized.get(), "");
+        assert_eq!(normalized.get_original(), "");
+    }
+
+    #[test]
+    fn train_from_word_counts() {
+        let mut tokenizer = Tokenizer::new(Box::new(BPE::default()));
+        let trainer: Box<dyn Trainer> = Box::new(
+            BpeTrainer::builder()
+                .show_progress(false)
+                .special_tokens(vec![AddedToken::from("[UNK]", true)])
+                .build(),
+        );
+        let word_counts: HashMap<String, u32> = [("hello".into(), 2), ("hell".into(), 1)]
+            .iter()
+            .cloned()
+            .collect();
+
+        tokenizer
+            .train_from_word_counts(&trainer, word_counts)
+            .unwrap();
+        assert_eq!(tokenizer.token_to_id("[UNK]"), Some(0));
+        assert!(tokenizer.token_to_id("hello").is_some());
+        assert!(tokenizer.added_vocabulary.is_special_token("[UNK]"));
+    }
+
+    #[test]
+    fn decode_without_decoder() {
+        let bpe = simple_bpe(&["a", "b"]);
+        let mut tokenizer = Tokenizer::new(Box::new(bpe));
+        tokenizer.add_special_tokens(&[AddedToken::from("[CLS]", true)]);
+
+        assert_eq!(tokenizer.decode(vec![2, 0, 1], false).unwrap(), "[CLS] a b");
+        assert_eq!(tokenizer.decode(vec![2, 0, 1], true).unwrap(), "a b");
+        assert_eq!(tokenizer.decode(vec![2], true).unwrap(), "");
+    }
+
+    #[test]
+    fn encode_successive_inputs() {
+        let bpe = simple_bpe(&["a", "b", "é"]);
+        let mut tokenizer = Tokenizer::new(Box::new(bpe));
+        tokenizer.add_special_tokens(&[AddedToken::from("[CLS]", true)]);
+
+        let encoding = tokenizer.encode("abéa", false).unwrap();
+        assert_eq!(encoding.get_tokens(), &["a", "b", "é", "a"]);
+
+        // The second input is shorter, and must not see anything from the first one
+        let encoding = tokenizer.encode("[CLS]b", false).unwrap();
+        assert_eq!(encoding.get_tokens(), &["[CLS]", "b"]);
+        assert_eq!(encoding.get_offsets(), &[(0, 5), (5, 6)]);
+
+        let encoding = tokenizer.encode("ba", false).unwrap();
+        assert_eq!(encoding.get_tokens(), &["b", "a"]);
+        assert_eq!(encoding.get_offsets(), &[(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn padding_mismatched_pad_token() {
+        let bpe = simple_bpe(&["[PAD]", "a"]);
+        let mut tokenizer = Tokenizer::new(Box::new(bpe));
+
+        // We only warn by default
+        let mut params = PaddingParams {
+            strategy: PaddingStrategy::Fixed(3),
+            pad_id: 1,
+            ..Default::default()
+        };
+        assert!(tokenizer.check_padding(&params).is_err());
+        tokenizer.with_padding(Some(params.clone()));
+        let encoding = tokenizer.encode("a", false).unwrap();
+        assert_eq!(encoding.get_ids(), &[1, 1, 1]);
+
+        params.strict = true;
+        tokenizer.with_padding(Some(params.clone()));
+        assert!(tokenizer.encode("a", false).is_err());
+        assert!(tokenizer.encode_batch(vec!["a"], false).is_err());
+
+        params.pad_id = 0;
+        tokenizer.with_padding(Some(params));
+        let encoding = tokenizer.encode("a", false).unwrap();
+        assert_eq!(encoding.get_ids(), &[1, 0, 0]);
+    }
+
+    #[test]
+    fn ids_to_tokens() {
+        let bpe = simple_bpe(&["a", "b"]);
+        let mut tokenizer = Tokenizer::new(Box::new(bpe));
+        tokenizer.add_special_tokens(&[AddedToken::from("[CLS]", true)]);
+
+        assert_eq!(
+            tokenizer.ids_to_tokens(&[2, 1, 3, 0]),
+            vec![
+                Some("[CLS]".to_owned()),
+                Some("b".to_owned()),
+                None,
+                Some("a".to_owned())
+            ]
+        );
+        assert!(tokenizer.ids_to_tokens(&[]).is_empty());
+    }
+
+    #[test]
+    fn decode_removed_special_tokens() {
+        let bpe = simple_bpe(&["▁Hello", "▁world"]);
+        let mut tokenizer = Tokenizer::new(Box::new(bpe));
+        tokenizer.with_decoder(Box::new(Metaspace::default()));
+        tokenizer.add_special_tokens(&[
+            AddedToken::from("[CLS]", true),
+            AddedToken::from("[SEP]", true),
+        ]);
+
+        assert_eq!(tokenizer.decode(vec![2, 0, 3], true).unwrap(), "Hello");
+        assert_eq!(
+            tokenizer.decode(vec![2, 0, 3, 1, 3], true).unwrap(),
+            "Hello world"
+        );
+        assert_eq!(
+            tokenizer.decode(vec![2, 0, 3], false).unwrap(),
+            "[CLS] Hello[SEP]"
+        );
+    }
+
+    #[test]
+    fn encode_skip_pretokenize() {
+        let merges: HashMap<(u32, u32), (u32, u32)> = [((0, 1), (0, 2))].iter().cloned().collect();
+        let bpe = BPE::builder()
+            .vocab_and_merges(vocab_of(&["a", "b", "ab"]), merges)
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(Box::new(bpe));
+        tokenizer.with_pre_tokenizer(Box::new(CharDelimiterSplit::new('b')));
+
+        let encoding = tokenizer.encode("ab", false).unwrap();
+        assert_eq!(encoding.get_tokens(), &["a"]);
+
+        let options = EncodeOptions {
+            skip_pretokenize: true,
+            ..Default::default()
+        };
+        let encoding = tokenizer.encode_with_options("ab", options).unwrap();
+        assert_eq!(encoding.get_tokens(), &["ab"]);
+        assert_eq!(encoding.get_offsets(), &[(0, 2)]);
+    }
+
+    #[test]
+    fn encode_batch_with_progress() {
+        let bpe = simple_bpe(&["a"]);
+        let tokenizer = Tokenizer::new(Box::new(bpe));
+
+        let reported = std::sync::Mutex::new(vec![]);
+        let encodings = tokenizer
+            .encode_batch_with_progress(vec!["a"; 10], false, 3, |done| {
+                reported.lock().unwrap().push(done)
+            })
+            .unwrap();
+        assert_eq!(encodings.len(), 10);
+
+        let mut reported = reported.into_inner().unwrap();
+        reported.sort_unstable();
+        assert_eq!(reported, vec![3, 6, 9, 10]);
+    }
+
+    #[test]
+    fn encode_batch_with_normalized() {
+        let bpe = simple_bpe(&["a", "b"]);
+        let mut tokenizer = Tokenizer::new(Box::new(bpe));
+        tokenizer.with_normalizer(Box::new(Lowercase));
+
+        let inputs = vec!["AB", "bA", "A"];
+        let results = tokenizer
+            .encode_batch_with_normalized(inputs.clone(), false)
+            .unwrap();
+        let encodings = tokenizer.encode_batch(inputs, false).unwrap();
+
+        assert_eq!(
+            results.iter().map(|(e, _)| e).cloned().collect::<Vec<_>>(),
+            encodings
+        );
+        assert_eq!(
+            results.iter().map(|(_, n)| n.get()).collect::<Vec<_>>(),
+            vec!["ab", "ba", "a"]
+        );
+        assert_eq!(results[1].1.get_original(), "bA");
+
+        // The normalized string is the one that went through the encoding, including the
+        // added tokens
+        tokenizer.add_special_tokens(&[AddedToken::from("[X]", true)]);
+        let (encoding, normalized) = tokenizer.encode_with_normalized("A[X]b", false).unwrap();
+        assert_eq!(encoding.get_tokens(), ["a", "[X]", "b"]);
+        assert_eq!(normalized.get(), "a[X]b");
+        assert_eq!(normalized, tokenizer.normalize("A[X]b").unwrap());
+    }
+
+    #[test]
+    fn encode_normalized() {
+        let bpe = simple_bpe(&["a", "b"]);
+        let mut tokenizer = Tokenizer::new(Box::new(bpe));
+        tokenizer.add_tokens(&[AddedToken::from("ab", false)]);
+
+        let mut normalized = NormalizedString::from("XAB");
+        normalized.filter(|c| *c != 'X').lowercase();
+        let encoding = tokenizer.encode_normalized(normalized, false).unwrap();
+
+        // The added token has not been extracted
+        assert_eq!(encoding.get_tokens(), &["a", "b"]);
+        assert_eq!(encoding.get_offsets(), &[(1, 2), (2, 3)]);
+    }
+
+    #[test]
+    fn vocab_size_with_added_tokens() {
+        let mut tokenizer = Tokenizer::new(Box::new(BPE::default()));
+        tokenizer.add_tokens(&[AddedToken::from("x", false)]);
+        assert_eq!(tokenizer.get_vocab_size(true), 1);
+
+        let bpe = simple_bpe(&["a", "b"]);
+        tokenizer.with_model(Box::new(bpe));
+        // `x` now shares its id with `a`
+        assert_eq!(tokenizer.get_vocab_size(false), 2);
+        assert_eq!(tokenizer.get_vocab_size(true), 2);
+
+        // Tokens already in the model don't increase the size either
+        tokenizer.add_tokens(&[AddedToken::from("b", false)]);
+        assert_eq!(tokenizer.get_vocab_size(true), 2);
+        tokenizer.add_tokens(&[AddedToken::from("y", false)]);
+        assert_eq!(tokenizer.get_vocab_size(true), 3);
+    }
+
+    #[test]
+    fn pre_tokenized_prefix_space_on_first_piece() {
+        let bpe = simple_bpe(&["Ġ", "H", "e", "l", "o", "w", "r", "d"]);
+        let mut tokenizer = Tokenizer::new(Box::new(bpe));
+        tokenizer.with_pre_tokenizer(Box::new(ByteLevel::default()));
+
+        let encoding = tokenizer.encode(&["Hello", "world"][..], false).unwrap();
+        assert_eq!(
+            encoding.get_tokens(),
+            &["Ġ", "H", "e", "l", "l", "o", "w", "o", "r", "l", "d"]
+        );
+        assert_eq!(encoding.get_words()[6], Some(1));
+    }
+
+    #[test]
+    fn tokenize_words_keeps_word_ids() {
+        let bpe = simple_bpe(&["a", "b", "c"]);
+
+        // "ab" and "c" are two parts of the same word, split by a previous step
+        let tokens = bpe
+            .tokenize_words(vec![
+                ("ab".into(), (0, 2), 0),
+                ("c".into(), (2, 3), 0),
+                ("a".into(), (4, 5), 1),
+            ])
+            .unwrap();
+        assert_eq!(
+            tokens.iter().map(|t| t.word).collect::<Vec<_>>(),
+            vec![0, 0, 0, 1]
+        );
+        assert_eq!(
+            tokens.iter().map(|t| t.offsets).collect::<Vec<_>>(),
+            vec![(0, 1), (1, 2), (2, 3), (4, 5)]
+        );
+    }
+
+    /// A model returning a token from a word it was not given
+    #[derive(Serialize, Deserialize)]
+    struct OutOfBoundsModel {
+        vocab: HashMap<String, u32>,
+    }
+
+    #[typetag::serde]
+    impl Model for OutOfBoundsModel {
+        fn tokenize(&self, tokens: Vec<(String, Offsets)>) -> Result<Vec<Token>> {
+            Ok(vec![Token::new(0, "a".into(), (0, 1), tokens.len() as u32)])
+        }
+        fn token_to_id(&self, token: &str) -> Option<u32> {
+            self.vocab.get(token).copied()
+        }
+        fn id_to_token(&self, _: u32) -> Option<&str> {
+            None
+        }
+        fn get_vocab(&self) -> &HashMap<String, u32> {
+            &self.vocab
+        }
+        fn get_vocab_size(&self) -> usize {
+            self.vocab.len()
+        }
+        fn save(&self, _: &Path, _: Option<&str>) -> Result<Vec<PathBuf>> {
+            Ok(vec![])
+        }
+    }
+
+    #[test]
+    fn tokenize_words_unknown_word() {
+        let tokenizer = Tokenizer::new(Box::new(OutOfBoundsModel {
+            vocab: HashMap::new(),
+        }));
+        let err = tokenizer.encode("a b", false).unwrap_err();
+        let err = err.downcast_ref::<UnknownWordError>().unwrap();
+        assert_eq!((err.word, err.words), (1, 1));
+    }
+
+    #[test]
+    fn words_count_limit() {
+        assert!(check_words_count(0).is_ok());
+        assert!(check_words_count(u32::MAX as usize).is_ok());
+        // Only reachable where `usize` is wider than `u32`
+        if let Some(words) = (u32::MAX as usize).checked_add(1) {
+            let err = check_words_count(words).unwrap_err();
+            let err = err.downcast_ref::<TooManyWordsError>().unwrap();
+            assert_eq!(err.words, words);
+        }
+    }
+
+    /// A model giving the last possible word index to the tokens of the first piece
+    #[derive(Serialize, Deserialize)]
+    struct LastWordModel {
+        vocab: HashMap<String, u32>,
+    }
+
+    #[typetag::serde]
+    impl Model for LastWordModel {
+        fn tokenize(&self, tokens: Vec<(String, Offsets)>) -> Result<Vec<Token>> {
+            Ok(tokens
+                .into_iter()
+                .enumerate()
+                .map(|(i, (word, offsets))| Token::new(0, word, offsets, i as u32))
+                .collect())
+        }
+        fn tokenize_words(&self, words: Vec<(String, Offsets, u32)>) -> Result<Vec<Token>> {
+            Ok(words
+                .into_iter()
+                .map(|(word, offsets, word_id)| {
+                    let word_id = if word_id == 0 { u32::MAX } else { word_id };
+                    Token::new(0, word, offsets, word_id)
+                })
+                .collect())
+        }
+        fn token_to_id(&self, token: &str) -> Option<u32> {
+            self.vocab.get(token).copied()
+        }
+        fn id_to_token(&self, _: u32) -> Option<&str> {
+            None
+        }
+        fn get_vocab(&self) -> &HashMap<String, u32> {
+            &self.vocab
+        }
+        fn get_vocab_size(&self) -> usize {
+            self.vocab.len()
+        }
+        fn save(&self, _: &Path, _: Option<&str>) -> Result<Vec<PathBuf>> {
+            Ok(vec![])
+        }
+    }
+
+    #[test]
+    fn words_count_limit_across_pieces() {
+        let tokenizer = Tokenizer::new(Box::new(LastWordModel {
+            vocab: HashMap::new(),
+        }));
+        // Each piece fits on its own, but not once merged after the first one
+        let err = tokenizer.encode(&["a", "b"][..], false).unwrap_err();
+        let err = err.downcast_ref::<TooManyWordsError>().unwrap();
+        assert_eq!(err.words, u32::MAX as usize + 2);
+    }
+
+    #[test]
+    fn tokens_aligned_after_special_tokens_and_padding() {
+        let bpe = simple_bpe(&["[PAD]", "[CLS]", "[SEP]", "a", "b"]);
+        let mut tokenizer = Tokenizer::new(Box::new(bpe));
+        tokenizer.with_post_processor(Box::new(BertProcessing::new(
+            ("[SEP]".into(), 2),
+            ("[CLS]".into(), 1),
+        )));
+        tokenizer.with_padding(Some(PaddingParams::default()));
+
+        let encodings = tokenizer
+            .encode_batch(vec![("a", "b"), ("ab", "ab")], true)
+            .unwrap();
+        for encoding in &encodings {
+            assert_eq!(encoding.get_tokens().len(), encoding.get_ids().len());
+            for (token, id) in encoding.get_tokens().iter().zip(encoding.get_ids()) {
+                assert_eq!(tokenizer.id_to_token(*id), Some(token.as_str()));
+            }
+        }
+        assert_eq!(
+            encodings[0].get_tokens(),
+            &["[CLS]", "a", "[SEP]", "b", "[SEP]", "[PAD]", "[PAD]"]
+        );
+    }
+
+    #[test]
+    fn tokenize() {
+        let bpe = simple_bpe(&["a", "b"]);
+        let mut tokenizer = Tokenizer::new(Box::new(bpe));
+        tokenizer.with_pre_tokenizer(Box::new(CharDelimiterSplit::new(' ')));
+        tokenizer.add_tokens(&[AddedToken::from("[X]", false)]);
+
+        let tokens = tokenizer.tokenize("ab [X]a").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(0, "a".into(), (0, 1), 0),
+                Token::new(1, "b".into(), (1, 2), 0),
+                Token::new(2, "[X]".into(), (3, 6), 1),
+                Token::new(0, "a".into(), (6, 7), 2),
+            ]
+        );
+
+        // The tokens are the ones `encode` would give
+        let input = &["ab", "[X]a"][..];
+        let tokens = tokenizer.tokenize(input).unwrap();
+        let encoding = tokenizer.encode(input, false).unwrap();
+        assert_eq!(
+            tokens.iter().map(|t| t.id).collect::<Vec<_>>(),
+            encoding.get_ids()
+        );
+        assert_eq!(
+            tokens.iter().map(|t| t.offsets).collect::<Vec<_>>(),
+            encoding.get_offsets()
+        );
+        assert_eq!(
+            tokens.iter().map(|t| Some(t.word)).collect::<Vec<_>>(),
+            encoding.get_words()
+        );
+    }
+
+    #[test]
+    fn save_pretrained() {
+        let bpe = simple_bpe(&["a", "b"]);
+        let tokenizer = Tokenizer::new(Box::new(bpe));
+        let folder = tempfile::tempdir().unwrap();
+
+        let files = tokenizer.save_pretrained(folder.path(), None).unwrap();
+        assert_eq!(
+            files,
+            vec![
+                folder.path().join("tokenizer.json"),
+                folder.path().join("vocab.json"),
+                folder.path().join("merges.txt"),
+            ]
+        );
+        let named = tokenizer
+            .save_pretrained(folder.path(), Some("other"))
+            .unwrap();
+        assert_eq!(named[0], folder.path().join("other-tokenizer.json"));
+        assert!(named.iter().all(|f| !files.contains(f) && f.exists()));
+
+        let loaded = Tokenizer::from_file(folder.path().join("tokenizer.json")).unwrap();
+        assert_eq!(loaded.get_vocab_size(false), 2);
+    }
+
+    #[test]
+    fn encode_batch_dedup() {
+        let bpe = simple_bpe(&["[PAD]", "a", "b"]);
+        let mut tokenizer = Tokenizer::new(Box::new(bpe));
+        tokenizer.with_padding(Some(PaddingParams::default()));
+
+        let inputs = vec!["a", "ab", "a", "ab", "a"];
+        let encodings = tokenizer.encode_batch_dedup(inputs.clone(), false).unwrap();
+        let expected = tokenizer.encode_batch(inputs, false).unwrap();
+        assert_eq!(
+            encodings.iter().map(|e| e.get_ids()).collect::<Vec<_>>(),
+            expected.iter().map(|e| e.get_ids()).collect::<Vec<_>>()
+        );
+        assert_eq!(encodings[4].get_ids(), &[1, 0]);
+    }
+
+    #[test]
+    fn truncate_pre_tokenized_at_words() {
+        let bpe = simple_bpe(&["a", "b", "c", "d", "e"]);
+        let mut tokenizer = Tokenizer::new(Box::new(bpe));
+        tokenizer.with_truncation(Some(TruncationParams {
+            max_length: 3,
+            stride: 1,
+            ..Default::default()
+        }));
+
+        let tokens = |e: &Encoding| e.get_tokens().to_vec();
+        let encoding = tokenizer.encode(&["ab", "cd", "e"][..], false).unwrap();
+        assert_eq!(tokens(&encoding), ["a", "b"]);
+        assert_eq!(
+            encoding
+                .get_overflowing()
+                .iter()
+                .map(tokens)
+                .collect::<Vec<_>>(),
+            [vec!["c", "d", "e"]]
+        );
+
+        // Raw inputs are cut on tokens
+        let encoding = tokenizer.encode("abcde", false).unwrap();
+        assert_eq!(tokens(&encoding), ["a", "b", "c"]);
+        assert_eq!(
+            encoding
+                .get_overflowing()
+                .iter()
+                .map(tokens)
+                .collect::<Vec<_>>(),
+            [vec!["c", "d", "e"]]
+        );
+    }
+
+    #[test]
+    fn downcast_components() {
+        let bpe = BPE::builder()
+            .vocab_and_merges(vocab_of(&["a", "b"]), HashMap::new())
+            .continuing_subword_prefix("##".into())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(Box::new(bpe));
+        tokenizer.with_normalizer(Box::new(Lowercase));
+
+        let bpe = tokenizer.model_as::<BPE>().unwrap();
+        assert_eq!(bpe.get_continuing_subword_prefix(), &Some("##".into()));
+        assert!(tokenizer.model_as::<WordPiece>().is_none());
+        assert!(tokenizer.normalizer_as::<Lowercase>().is_some());
+        assert!(tokenizer.normalizer_as::<Strip>().is_none());
+    }
+
+    #[test]
+    fn downcast_after_round_trip() {
+        let bpe = simple_bpe(&["[SEP]", "[CLS]"]);
+        let mut tokenizer = Tokenizer::new(Box::new(bpe));
+        tokenizer.with_pre_tokenizer(Box::new(Metaspace::new('_', true)));
+        tokenizer.with_post_processor(Box::new(BertProcessing::new(
+            ("[SEP]".into(), 0),
+            ("[CLS]".into(), 1),
+        )));
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        tokenizer
+            .save(file.path().to_str().unwrap(), false)
+            .unwrap();
+        let tokenizer = Tokenizer::from_file(file.path()).unwrap();
+
+        let metaspace = tokenizer.pre_tokenizer_as::<Metaspace>().unwrap();
+        assert_eq!(metaspace.get_replacement(), '_');
+        assert!(tokenizer.pre_tokenizer_as::<ByteLevel>().is_none());
+        let processor = tokenizer.post_processor_as::<BertProcessing>().unwrap();
+        assert_eq!(processor.get_cls(), &(String::from("[CLS]"), 1));
+    }
+
+    #[test]
+    fn get_special_tokens() {
+        let bpe = simple_bpe(&["a", "[UNK]"]);
+        let mut tokenizer = Tokenizer::new(Box::new(bpe));
+        tokenizer.add_tokens(&[AddedToken::from("b", false)]);
+        tokenizer.add_special_tokens(&[
+            AddedToken::from("[CLS]", true),
+            AddedToken::from("[UNK]", true),
+        ]);
+
+        assert_eq!(tokenizer.get_special_tokens(), ["[CLS]", "[UNK]"]);
+        assert_eq!(tokenizer.get_special_tokens_ids(), [3, 1]);
+    }
+
+    #[test]
+    fn line_endings_like_training() {
+        let mut tokenizer = Tokenizer::new(Box::new(BPE::default()));
+        tokenizer.with_pre_tokenizer(Box::new(ByteLevel::default()));
+        let trainer: Box<dyn Trainer> =
+            Box::new(BpeTrainer::builder().show_progress(false).build());
+
+        let input = "Hello there\r\nGeneral  Kenobi\n";
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(input.as_bytes()).unwrap();
+        let counts = tokenizer
+            .word_count(&trainer, vec![file.path().to_str().unwrap().to_owned()])
+            .unwrap();
+
+        // The whole input at once doesn't match the training
+        let words = tokenizer
+            .pre_tokenize(&mut NormalizedString::from(input))
+            .unwrap();
+        assert!(!words.iter().any(|(w, _)| w == "čĊ"));
+
+        let mut normalized = NormalizedString::from(input);
+        let words = tokenizer
+            .pre_tokenize_lines(&mut normalized, false)
+            .unwrap();
+        let mut expected = HashMap::new();
+        for (word, _) in &words {
+            *expected.entry(word.clone()).or_insert(0) += 1;
+        }
+        assert_eq!(counts, expected);
+        assert!(words.iter().any(|(w, _)| w == "čĊ"));
+
+        // Each line gets its own prefix space, but offsets still refer to the whole sequence
+        let (general, offsets) = &words[3];
+        assert_eq!(general, "ĠGeneral");
+        assert_eq!(
+            normalized.convert_offsets(Range::Normalized(offsets.0..offsets.1)),
+            Some(13..20)
+        );
+    }
+
+    #[test]
+    fn builder_with_added_tokens() {
+        let tokenizer = Tokenizer::builder(Box::new(
+            BPE::builder()
+                .vocab_and_merges(vocab_of(&["[UNK]", "a", "b"]), HashMap::new())
+                .unk_token("[UNK]".into())
+                .build()
+                .unwrap(),
+        ))
+        .with_normalizer(Box::new(Lowercase))
+        .with_added_tokens(vec![AddedToken::from("AB", false)])
+        .with_special_tokens(vec![
+            AddedToken::from("[UNK]", true),
+            AddedToken::from("[SEP]", true),
+        ])
+        .build();
+
+        assert_eq!(tokenizer.get_vocab_size(true), 5);
+        assert_eq!(tokenizer.token_to_id("[SEP]"), Some(3));
+        assert_eq!(tokenizer.token_to_id("AB"), Some(4));
+        assert_eq!(tokenizer.get_special_tokens(), ["[UNK]", "[SEP]"]);
+
+        let encoding = tokenizer.encode("aAbb[SEP]", false).unwrap();
+        assert_eq!(encoding.get_tokens(), ["a", "ab", "b", "[SEP]"]);
+    }
+
+    #[test]
+    fn builder_with_padding() {
+        let padding = PaddingParams {
+            strategy: PaddingStrategy::Fixed(3),
+            pad_id: 2,
+            strict: true,
+            ..Default::default()
+        };
+        // The padding token is only part of the vocabulary once the special tokens are added
+        let tokenizer = Tokenizer::builder(Box::new(simple_bpe(&["a", "b"])))
+            .with_padding(Some(padding))
+            .with_special_tokens(vec![AddedToken::from("[PAD]", true)])
+            .build();
+        assert!(tokenizer
+            .check_padding(tokenizer.get_padding().unwrap())
+            .is_ok());
+
+        let encoding = tokenizer.encode("a", false).unwrap();
+        assert_eq!(encoding.get_tokens(), ["a", "[PAD]", "[PAD]"]);
+    }
+
+    #[test]
+    fn encode_with_type_id() {
+        let mut tokenizer = Tokenizer::new(Box::new(
+            BPE::builder()
+                .vocab_and_merges(vocab_of(&["[UNK]", "[CLS]", "[SEP]", "a"]), HashMap::new())
+                .unk_token("[UNK]".into())
+                .build()
+                .unwrap(),
+        ));
+        tokenizer.with_pre_tokenizer(Box::new(CharDelimiterSplit::new(' ')));
+
+        let encoding = tokenizer.encode_with_type_id("a a", 2, false).unwrap();
+        assert_eq!(encoding.get_type_ids(), [2, 2]);
+        let encoding = tokenizer
+            .encode_with_type_id(&["a", "a"][..], 3, false)
+            .unwrap();
+        assert_eq!(encoding.get_type_ids(), [3, 3]);
+
+        tokenizer.with_post_processor(Box::new(BertProcessing::new(
+            ("[SEP]".into(), 2),
+            ("[CLS]".into(), 1),
+        )));
+        let encoding = tokenizer.encode_with_type_id("a a", 2, true).unwrap();
+        assert_eq!(encoding.get_tokens(), ["[CLS]", "a", "a", "[SEP]"]);
+        assert_eq!(encoding.get_type_ids(), [0, 2, 2, 0]);
+    }
+
+    #[test]
+    fn keep_normalized_offsets() {
+        let mut tokenizer =
+            Tokenizer::new(Box::new(simple_bpe(&["[CLS]", "[SEP]", "a", "b", "c"])));
+        tokenizer.with_normalizer(Box::new(Strip::new(true, true)));
+        tokenizer.with_pre_tokenizer(Box::new(CharDelimiterSplit::new(' ')));
+        tokenizer.with_post_processor(Box::new(BertProcessing::new(
+            ("[SEP]".into(), 1),
+            ("[CLS]".into(), 0),
+        )));
+
+        let encoding = tokenizer.encode("  ab c", true).unwrap();
+        assert!(encoding.get_normalized_offsets().is_empty());
+
+        let options = EncodeOptions {
+            add_special_tokens: true,
+            keep_normalized_offsets: true,
+            ..Default::default()
+        };
+        let encoding = tokenizer.encode_with_options("  ab c", options).unwrap();
+        assert_eq!(encoding.get_tokens(), ["[CLS]", "a", "b", "c", "[SEP]"]);
+        assert_eq!(
+            encoding.get_offsets(),
+            [(0, 0), (2, 3), (3, 4), (5, 6), (0, 0)]
+        );
+        assert_eq!(
+            encoding.get_normalized_offsets(),
+            [(0, 0), (0, 1), (1, 2), (3, 4), (0, 0)]
+        );
+    }
+
+    #[test]
+    fn encode_with_prefix() {
+        let merges = vec![((2, 3), (0, 5))].into_iter().collect();
+        let mut tokenizer = Tokenizer::new(Box::new(
+            BPE::builder()
+                .vocab_and_merges(vocab_of(&["[CLS]", "[SEP]", "a", "b", "c", "ab"]), merges)
+                .build()
+                .unwrap(),
+        ));
+        tokenizer.with_pre_tokenizer(Box::new(CharDelimiterSplit::new(' ')));
+        tokenizer.with_post_processor(Box::new(BertProcessing::new(
+            ("[SEP]".into(), 1),
+            ("[CLS]".into(), 0),
+        )));
+
+        let prefix = "ab ca ";
+        let prefix_encoding = tokenizer.encode(prefix, false).unwrap();
+        for suffix in &["b ac", "bc a", ""] {
+            let encoding = tokenizer
+                .encode_with_prefix(prefix, &prefix_encoding, suffix, true)
+                .unwrap();
+            let expected = tokenizer
+                .encode(format!("{}{}", prefix, suffix), true)
+                .unwrap();
+            assert_eq!(encoding, expected);
+        }
+
+        // The prefix encoding must not contain any special token
+        let with_specials = tokenizer.encode(prefix, true).unwrap();
+        assert!(tokenizer
+            .encode_with_prefix(prefix, &with_specials, "b", false)
+            .is_err());
+
+        // The last word of the prefix gets tokenized differently with the suffix
+        tokenizer.with_pre_tokenizer(Box::new(CharDelimiterSplit::new('c')));
+        let prefix_encoding = tokenizer.encode("ca", false).unwrap();
+        assert!(tokenizer
+            .encode_with_prefix("ca", &prefix_encoding, "b", false)
+            .is_err());
+    }
+
+    #[test]
+    fn decode_metaspace_after_special_tokens() {
+        let mut tokenizer = Tokenizer::new(Box::new(simple_bpe(&["[CLS]", "▁Hello", "▁world"])));
+        tokenizer.with_decoder(Box::new(Metaspace::default()));
+        tokenizer.add_special_tokens(&[AddedToken::from("[CLS]", true)]);
+
+        assert_eq!(
+            tokenizer.decode(vec![0, 1, 2], true).unwrap(),
+            "Hello world"
+        );
+        assert_eq!(
+            tokenizer.decode(vec![0, 1, 2], false).unwrap(),
+            "[CLS] Hello world"
+        );
+    }
+
+    #[test]
+    fn count_tokens() {
+        let mut tokenizer = Tokenizer::new(Box::new(simple_bpe(&["[CLS]", "[SEP]", "a", "b"])));
+        tokenizer.with_pre_tokenizer(Box::new(CharDelimiterSplit::new(' ')));
+        tokenizer.add_tokens(&[AddedToken::from("ab", false)]);
+        tokenizer.with_post_processor(Box::new(BertProcessing::new(
+            ("[SEP]".into(), 1),
+            ("[CLS]".into(), 0),
+        )));
+
+        let inputs: Vec<EncodeInput> = vec![
+            "".into(),
+            "a bab ba".into(),
+            ("a b", "bbab").into(),
+            (&["ab", "ba"][..]).into(),
+        ];
+        for input in inputs {
+            for &add_special_tokens in &[false, true] {
+                assert_eq!(
+                    tokenizer
+                        .count_tokens(input.clone(), add_special_tokens)
+                        .unwrap(),
+                    tokenizer
+                        .encode(input.clone(), add_special_tokens)
+                        .unwrap()
+                        .len()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn reject_nul_bytes() {
+        let tokenizer = Tokenizer::new(Box::new(simple_bpe(&["a", "\0"])));
+
+        let encoding = tokenizer.encode("a\0a", false).unwrap();
+        assert_eq!(encoding.get_tokens(), ["a", "\0", "a"]);
+
+        let options = EncodeOptions {
+            reject_nul_bytes: true,
+            ..Default::default()
+        };
+        assert!(tokenizer.encode_with_options("aa", options).is_ok());
+        let err = tokenizer.encode_with_options("a\0a", options).unwrap_err();
+        let err = err.downcast_ref::<NulByteError>().unwrap();
+        assert_eq!((err.token, err.value.as_str()), (1, "\0"));
+    }
+
+    #[test]
+    fn error_on_overflow() {
+        let mut tokenizer = Tokenizer::new(Box::new(simple_bpe(&["[CLS]", "[SEP]", "a"])));
+        tokenizer.with_post_processor(Box::new(BertProcessing::new(
+            ("[SEP]".into(), 1),
+            ("[CLS]".into(), 0),
+        )));
+        tokenizer.with_truncation(Some(TruncationParams {
+            max_length: 5,
+            ..Default::default()
+        }));
+
+        let options = EncodeOptions {
+            add_special_tokens: true,
+            error_on_overflow: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            tokenizer.encode_with_options("aaa", options).unwrap().len(),
+            5
+        );
+        let err = tokenizer.encode_with_options("aaaa", options).unwrap_err();
+        let err = err.downcast_ref::<OverflowError>().unwrap();
+        assert_eq!((err.length, err.max_length), (4, 3));
+        assert!(tokenizer.encode_with_options(("aa", "a"), options).is_err());
+
+        // Without the special tokens, the whole length is available
+        let options = EncodeOptions {
+            add_special_tokens: false,
+            ..options
+        };
+        assert!(tokenizer.encode_with_options("aaaa", options).is_ok());
+        assert!(tokenizer.encode_with_options("aaaaaa", options).is_err());
+
+        // And the usual truncation still applies otherwise
+        let encoding = tokenizer.encode("aaaaaa", true).unwrap();
+        assert_eq!(encoding.len(), 5);
+    }
+
+    #[test]
+    fn encode_ids() {
+        let mut tokenizer = Tokenizer::new(Box::new(simple_bpe(&["[CLS]", "[SEP]", "a", "b"])));
+        tokenizer.with_pre_tokenizer(Box::new(CharDelimiterSplit::new(' ')));
+        tokenizer.add_tokens(&[AddedToken::from("ab", false)]);
+        tokenizer.with_post_processor(Box::new(BertProcessing::new(
+            ("[SEP]".into(), 1),
+            ("[CLS]".into(), 0),
+        )));
+
+        let inputs: Vec<EncodeInput> = vec![
+            "".into(),
+            "a bab ba".into(),
+            ("a b", "bbab").into(),
+            (&["ab", "ba", "b"][..]).into(),
+        ];
+        let check = |tokenizer: &Tokenizer, inputs: &[EncodeInput]| {
+            for input in inputs {
+                for &add_special_tokens in &[false, true] {
+                    assert_eq!(
+                        tokenizer
+                            .encode_ids(input.clone(), add_special_tokens)
+                            .unwrap(),
+                        tokenizer
+                            .encode(input.clone(), add_special_tokens)
+                            .unwrap()
+                            .get_ids()
+                    );
+                }
+            }
+        };
+        check(&tokenizer, &inputs);
+
+        // Truncating at words for the pre-tokenized input
+        tokenizer.with_truncation(Some(TruncationParams {
+            max_length: 5,
+            ..Default::default()
+        }));
+        check(&tokenizer, &inputs[1..]);
+    }
+
+    #[test]
+    fn combining_marks_stay_with_their_base() {
+        use crate::pre_tokenizers::bert::BertPreTokenizer;
+        use crate::pre_tokenizers::whitespace::{Whitespace, WhitespaceSplit};
+
+        let merges = vec![((0, 1), (0, 3))].into_iter().collect();
+        let mut tokenizer = Tokenizer::new(Box::new(
+            BPE::builder()
+                .vocab_and_merges(vocab_of(&["a", "\u{301}", "b", "a\u{301}"]), merges)
+                .build()
+                .unwrap(),
+        ));
+
+        // The ByteLevel pre-tokenizer is not part of these, as it keeps the GPT-2 behavior of
+        // splitting the marks from their base character
+        let pre_tokenizers: Vec<Box<dyn PreTokenizer>> = vec![
+            Box::new(Whitespace),
+            Box::new(WhitespaceSplit),
+            Box::new(BertPreTokenizer),
+        ];
+        for pre_tokenizer in pre_tokenizers {
+            tokenizer.with_pre_tokenizer(pre_tokenizer);
+            let encoding = tokenizer.encode("a\u{301} b", false).unwrap();
+            assert_eq!(encoding.get_tokens(), ["a\u{301}", "b"]);
+            assert_eq!(encoding.get_offsets(), [(0, 2), (3, 4)]);
+        }
+    }
+
+    #[test]
+    fn reconstruct() {
+        let mut tokenizer = Tokenizer::new(Box::new(
+            WordPiece::builder()
+                .vocab(vocab_of(&[
+                    "[UNK]", "[CLS]", "[SEP]", "hello", ",", "world", "##s", "!",
+                ]))
+                .build()
+                .unwrap(),
+        ));
+        tokenizer.with_normalizer(Box::new(crate::normalizers::bert::BertNormalizer::default()));
+        tokenizer.with_pre_tokenizer(Box::new(crate::pre_tokenizers::bert::BertPreTokenizer));
+        tokenizer.with_post_processor(Box::new(BertProcessing::new(
+            ("[SEP]".into(), 2),
+            ("[CLS]".into(), 1),
+        )));
+
+        let input = "Héllo,  Worlds !";
+        let encoding = tokenizer.encode(input, true).unwrap();
+        assert_eq!(
+            encoding.get_tokens(),
+            ["[CLS]", "hello", ",", "world", "##s", "!", "[SEP]"]
+        );
+        assert_eq!(
+            tokenizer.reconstruct(&encoding, input),
+            input.split_whitespace().collect::<String>()
+        );
+    }
+
+    #[test]
+    fn wordpiece_alignments() {
+        let mut tokenizer = Tokenizer::new(Box::new(
+            WordPiece::builder()
+                .vocab(vocab_of(&[
+                    "[UNK]", "[CLS]", "[SEP]", "un", "##aff", "##able", "able", "a",
+                ]))
+                .build()
+                .unwrap(),
+        ));
+        tokenizer.with_pre_tokenizer(Box::new(crate::pre_tokenizers::bert::BertPreTokenizer));
+        tokenizer.with_post_processor(Box::new(BertProcessing::new(
+            ("[SEP]".into(), 2),
+            ("[CLS]".into(), 1),
+        )));
+
+        let encoding = tokenizer.encode(("unaffable a", "able"), true).unwrap();
+        assert_eq!(
+            encoding.get_tokens(),
+            ["[CLS]", "un", "##aff", "##able", "a", "[SEP]", "able", "[SEP]"]
+        );
+
+        // The pieces of a word make a contiguous span of tokens
+        assert_eq!(encoding.word_to_tokens(0), Some((1, 4)));
+        assert_eq!(encoding.word_to_tokens(1), Some((4, 5)));
+        // The words of the pair come after the ones of the first sequence
+        assert_eq!(encoding.word_to_tokens(2), Some((6, 7)));
+        assert_eq!(encoding.word_to_tokens(3), None);
+
+        assert_eq!(encoding.token_to_word(0), None);
+        assert_eq!(encoding.token_to_word(2), Some(0));
+        assert_eq!(encoding.token_to_word(3), Some(0));
+        assert_eq!(encoding.token_to_word(5), None);
+        assert_eq!(encoding.token_to_word(6), Some(2));
+        assert_eq!(encoding.token_to_word(8), None);
+
+        assert_eq!(encoding.char_to_token(0), Some(1));
+        assert_eq!(encoding.char_to_token(4), Some(2));
+        assert_eq!(encoding.char_to_token(8), Some(3));
+        assert_eq!(encoding.char_to_token(9), None);
+        assert_eq!(encoding.char_to_token(10), Some(4));
+        assert_eq!(encoding.char_to_word(6), Some(0));
+    }
+
+    #[test]
+    fn word_ids() {
+        let mut tokenizer = Tokenizer::new(Box::new(
+            WordPiece::builder()
+                .vocab(vocab_of(&[
+                    "[UNK]", "[CLS]", "[SEP]", "[PAD]", "hello", "world", "##s",
+                ]))
+                .build()
+                .unwrap(),
+        ));
+        tokenizer.with_pre_tokenizer(Box::new(crate::pre_tokenizers::bert::BertPreTokenizer));
+        tokenizer.with_post_processor(Box::new(BertProcessing::new(
+            ("[SEP]".into(), 2),
+            ("[CLS]".into(), 1),
+        )));
+        tokenizer.with_padding(Some(PaddingParams {
+            strategy: PaddingStrategy::Fixed(7),
+            pad_id: 3,
+            ..Default::default()
+        }));
+
+        let encoding = tokenizer.encode("hello worlds", true).unwrap();
+        assert_eq!(
+            encoding.get_tokens(),
+            ["[CLS]", "hello", "world", "##s", "[SEP]", "[PAD]", "[PAD]"]
+        );
+        assert_eq!(
+            encoding.get_word_ids(),
+            [None, Some(0), Some(1), Some(1), None, None, None]
+        );
+    }
+
+    #[test]
+    fn decode_batch_with() {
+        let mut tokenizer = Tokenizer::new(Box::new(
+            WordPiece::builder()
+                .vocab(vocab_of(&[
+                    "[UNK]", "[CLS]", "[SEP]", "hello", ",", "i", "'m", "here",
+                ]))
+                .build()
+                .unwrap(),
+        ));
+        tokenizer.add_special_tokens(&[
+            AddedToken::from("[CLS]", true),
+            AddedToken::from("[SEP]", true),
+        ]);
+        let sentences = vec![vec![1, 3, 4, 5, 6, 7, 2], vec![1, 7, 0, 2]];
+
+        assert_eq!(
+            tokenizer
+                .decode_batch_with(sentences.clone(), DecodeOptions::default())
+                .unwrap(),
+            ["[CLS] hello , i 'm here [SEP]", "[CLS] here [UNK] [SEP]"]
+        );
+
+        let options = DecodeOptions {
+            skip_special_tokens: true,
+            clean_up_tokenization_spaces: true,
+            skip_ids: [0].iter().copied().collect(),
+        };
+        assert_eq!(
+            tokenizer.decode_batch_with(sentences, options).unwrap(),
+            ["hello, i'm here", "here"]
+        );
+    }
+
+    #[test]
+    fn encode_bytes() {
+        let tokenizer = Tokenizer::new(Box::new(simple_bpe(&["a", "b", "?"])));
+
+        let encoding = tokenizer
+            .encode_bytes(b"a\xffb", InvalidUtf8::Replace('?'), false)
+            .unwrap();
+        assert_eq!(encoding.get_tokens(), ["a", "?", "b"]);
+        assert_eq!(encoding.get_offsets(), [(0, 1), (1, 2), (2, 3)]);
+
+        let encoding = tokenizer
+            .encode_bytes(b"a\xffb", InvalidUtf8::Drop, false)
+            .unwrap();
+        assert_eq!(encoding.get_tokens(), ["a", "b"]);
+    }
+
+    #[test]
+    fn encode_file() {
+        let tokenizer = Tokenizer::new(Box::new(simple_bpe(&["a", "b", "\n"])));
+
+        // Enough lines to span several chunks
+        let input = format!("{}b", "ab\n".repeat(2_500));
+        let encodings = tokenizer
+            .encode_file(std::io::Cursor::new(input), false)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(encodings.len(), 2_501);
+        assert!(encodings[..2_500]
+            .iter()
+            .all(|encoding| encoding.get_tokens() == ["a", "b", "\n"]));
+        assert_eq!(encodings[2_500].get_tokens(), ["b"]);
+
+        // Invalid UTF-8 can't be read, but only fails its own line
+        let results = tokenizer
+            .encode_file(&b"a\n\xff\nb"[..], false)
+            .collect::<Vec<_>>();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().get_tokens(), ["a", "\n"]);
+        assert!(results[1].is_err());
+        assert_eq!(results[2].as_ref().unwrap().get_tokens(), ["b"]);
+    }
+
+    #[test]
+    fn pre_tokenized_with_offsets() {
+        let tokenizer = Tokenizer::new(Box::new(simple_bpe(&["a", "b", "c"])));
+        let input =
+            |offsets: Vec<Offsets>| InputSequence::PreTokenizedWithOffsets("ab c".into(), offsets);
+
+        // The offsets are relative to the original string
+        let encoding = tokenizer
+            .encode(input(vec![(0, 2), (3, 4)]), false)
+            .unwrap();
+        assert_eq!(encoding.get_tokens(), ["a", "b", "c"]);
+        assert_eq!(encoding.get_offsets(), [(0, 1), (1, 2), (3, 4)]);
+        assert_eq!(encoding.get_words(), [Some(0), Some(0), Some(1)]);
+
+        let err = tokenizer
+            .encode(input(vec![(0, 2), (1, 4)]), false)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Offsets error: Offsets (1, 4) of word 1 in the input are out of bounds or overlap \
+             the previous word"
+        );
+        let err = tokenizer
+            .encode(input(vec![(0, 2), (3, 5)]), false)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Offsets error: Offsets (3, 5) of word 1 in the input are out of bounds or overlap \
+             the previous word"
+        );
+        assert!(tokenizer.encode(input(vec![(2, 1)]), false).is_err());
+    }
+
+    #[cfg(all(debug_assertions, feature = "validate-offsets"))]
+    #[test]
+    fn overlapping_pre_tokenized_offsets() {
+        let mut tokenizer = Tokenizer::new(Box::new(simple_bpe(&["a", "b", "c"])));
+        tokenizer.with_pre_tokenizer(Box::new(OverlappingPreTokenizer));
+
+        let err = tokenizer.encode("abc", false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Offsets error: Offsets (1, 3) of word 1 returned by OverlappingPreTokenizer are out \
+             of bounds or overlap the previous word"
+        );
+    }
+
+    #[cfg(all(debug_assertions, feature = "validate-offsets"))]
+    #[test]
+    fn normalized_tokens_offsets() {
+        let mut tokenizer = Tokenizer::new(Box::new(simple_bpe(&["a", "e"])));
+        tokenizer.with_normalizer(Box::new(Replace::new("é", "e").unwrap()));
+
+        // The token doesn't match the original string, but its offsets are right
+        let encoding = tokenizer.encode("aé", false).unwrap();
+        assert_eq!(encoding.get_tokens(), ["a", "e"]);
+        assert_eq!(encoding.get_offsets(), [(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn check_vocab_roundtrip() {
+        let vocab: HashMap<String, u32> = [("a", 0), ("b", 1), ("c", 1), ("d", 2)]
+            .iter()
+            .map(|(t, id)| (t.to_string(), *id))
+            .collect();
+        let mut tokenizer = Tokenizer::new(Box::new(
+            BPE::builder()
+                .vocab_and_merges(vocab, HashMap::new())
+                .build()
+                .unwrap(),
+        ));
+        tokenizer.add_tokens(&[AddedToken::from("e", false)]);
+
+        // Only one of the tokens sharing the id 1 can be returned by `id_to_token`
+        let inconsistent = tokenizer.check_vocab_roundtrip();
+        assert_eq!(inconsistent.len(), 1);
+        let (token, id) = &inconsistent[0];
+        assert_eq!(*id, 1);
+        assert_ne!(tokenizer.id_to_token(1), Some(token.as_str()));
+    }
+
+    #[test]
+    fn unk_mask() {
+        use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+
+        let mut tokenizer = Tokenizer::new(Box::new(
+            WordPiece::builder()
+                .vocab(vocab_of(&["[UNK]", "[CLS]", "[SEP]", "a", "b", "##b"]))
+                .build()
+                .unwrap(),
+        ));
+        tokenizer.with_pre_tokenizer(Box::new(WhitespaceSplit));
+        tokenizer.with_post_processor(Box::new(BertProcessing::new(
+            ("[SEP]".into(), 2),
+            ("[CLS]".into(), 1),
+        )));
+
+        let encoding = tokenizer.encode("ab c [UNK]", true).unwrap();
+        assert!(encoding.get_unk_mask().is_empty());
+
+        let options = EncodeOptions {
+            add_special_tokens: true,
+            keep_unk_mask: true,
+            ..Default::default()
+        };
+        // Only the unknown "c" is flagged, not the "[UNK]" found as is in the vocabulary
+        let encoding = tokenizer
+            .encode_with_options("ab c [UNK]", options)
+            .unwrap();
+        assert_eq!(
+            encoding.get_tokens(),
+            ["[CLS]", "a", "##b", "[UNK]", "[UNK]", "[SEP]"]
+        );
+        assert_eq!(
+            encoding.get_unk_mask(),
+            [false, false, false, true, false, false]
+        );
+    }
+
+    #[test]
+    fn freeze_added_vocabulary() {
+        let mut tokenizer = Tokenizer::new(Box::new(
+            WordPiece::builder()
+                .vocab(vocab_of(&["a", "b"]))
+                .build()
+                .unwrap(),
+        ));
+        assert_eq!(tokenizer.add_tokens(&[AddedToken::from("[A]", false)]), 1);
+        assert!(!tokenizer.is_frozen());
+
+        tokenizer.freeze_added_vocabulary();
+        assert!(tokenizer.is_frozen());
+        assert_eq!(tokenizer.add_tokens(&[AddedToken::from("[B]", false)]), 0);
+        assert_eq!(
+            tokenizer.add_special_tokens(&[AddedToken::from("[C]", true)]),
+            0
+        );
+        assert_eq!(tokenizer.token_to_id("[A]"), Some(2));
+        assert_eq!(tokenizer.token_to_id("[B]"), None);
+        assert_eq!(tokenizer.token_to_id("[C]"), None);
+        assert_eq!(tokenizer.get_vocab_size(true), 3);
+    }
 }
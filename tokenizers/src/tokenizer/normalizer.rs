@@ -1,3 +1,6 @@
+use super::Offsets;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::ops::{Bound, RangeBounds};
 use unicode_normalization_alignments::UnicodeNormalization;
@@ -385,6 +388,82 @@ impl NormalizedString {
         self
     }
 
+    /// Replace each occurrence of `pattern` with `content`. The chars of the content are aligned
+    /// with the ones of the pattern they replace, and any extra char with the last of them.
+    pub fn replace(&mut self, pattern: &str, content: &str) -> &mut Self {
+        if pattern.is_empty() {
+            return self;
+        }
+        let matches = self
+            .normalized
+            .match_indices(pattern)
+            .map(|(start, m)| (start, start + m.len()))
+            .collect::<Vec<_>>();
+        self.replace_matches(matches, content)
+    }
+
+    /// Replace each match of `regex` with `content`, aligned just like with `replace`. The
+    /// empty matches are ignored.
+    pub fn replace_regex(&mut self, regex: &regex::Regex, content: &str) -> &mut Self {
+        let matches = regex
+            .find_iter(&self.normalized)
+            .filter(|m| m.start() < m.end())
+            .map(|m| (m.start(), m.end()))
+            .collect::<Vec<_>>();
+        self.replace_matches(matches, content)
+    }
+
+    /// Replace each of the given non-overlapping, ordered and non-empty byte ranges of the
+    /// normalized string with `content`
+    fn replace_matches(&mut self, matches: Vec<(usize, usize)>, content: &str) -> &mut Self {
+        let mut new_chars: Vec<(char, isize)> = vec![];
+        let mut initial_offset = 0;
+        let mut last_end = 0;
+        for (start, end) in matches {
+            new_chars.extend(self.normalized[last_end..start].chars().map(|c| (c, 0)));
+            let pattern_len = self.normalized[start..end].chars().count();
+
+            let mut replacement = content
+                .chars()
+                .enumerate()
+                .map(|(index, c)| (c, if index < pattern_len { 0 } else { 1 }))
+                .collect::<Vec<_>>();
+            let removed = pattern_len.saturating_sub(replacement.len());
+            if removed > 0 {
+                // The chars removed come right after the last one we keep
+                match replacement.last_mut().or_else(|| new_chars.last_mut()) {
+                    Some((_, change)) => *change -= removed as isize,
+                    None => initial_offset += removed,
+                }
+            }
+            new_chars.extend(replacement);
+            last_end = end;
+        }
+        new_chars.extend(self.normalized[last_end..].chars().map(|c| (c, 0)));
+
+        self.transform(new_chars.into_iter(), initial_offset);
+        self
+    }
+
+    /// Case fold, for caseless matching. The folding is computed from the full case mappings,
+    /// lowercasing the uppercase form of each char twice, which gives the full case folding of
+    /// most chars (`ß` => `ss`, `ς` => `σ`, ...)
+    pub fn case_fold(&mut self) -> &mut Self {
+        let mut new_chars: Vec<(char, isize)> = vec![];
+        self.for_each(|c| {
+            c.to_uppercase()
+                .flat_map(char::to_lowercase)
+                .flat_map(char::to_uppercase)
+                .flat_map(char::to_lowercase)
+                .enumerate()
+                .for_each(|(index, c)| {
+                    new_chars.push((c, if index > 0 { 1 } else { 0 }));
+                })
+        });
+        self.transform(new_chars.into_iter(), 0);
+        self
+    }
+
     /// Split off ourselves, returning a new Self that contains the range [at, len).
     /// self will then contain the range [0, at).
     /// The provided `at` indexes on `char` not bytes.
@@ -432,10 +511,95 @@ impl NormalizedString {
         }
     }
 
+    /// Split the normalized string on the matches of the given `Pattern`, deciding what to do
+    /// with the matched delimiters according to `behavior`. Each piece is a `NormalizedString`
+    /// of its own, keeping the alignments with its part of the original string, and comes with
+    /// its offsets in our normalized string (indexing on `char`, like the `PreTokenizer`s).
+    pub fn split<P: Pattern>(
+        &self,
+        pattern: P,
+        behavior: SplitDelimiterBehavior,
+    ) -> Vec<(NormalizedString, Offsets)> {
+        let len = self.normalized.len();
+
+        // Cover the whole string, with delimiters flagged as matches
+        let mut splits = Vec::new();
+        let mut last = 0;
+        for m in pattern.find_matches(&self.normalized) {
+            if m.start == m.end || m.start < last {
+                continue;
+            }
+            if last < m.start {
+                splits.push((last..m.start, false));
+            }
+            last = m.end;
+            splits.push((m, true));
+        }
+        if last < len {
+            splits.push((last..len, false));
+        }
+
+        let mut previous_match = false;
+        let splits = match behavior {
+            SplitDelimiterBehavior::Removed => splits
+                .into_iter()
+                .filter(|(_, is_match)| !is_match)
+                .map(|(range, _)| range)
+                .collect::<Vec<_>>(),
+            SplitDelimiterBehavior::Isolated => {
+                splits.into_iter().map(|(range, _)| range).collect()
+            }
+            SplitDelimiterBehavior::MergedWithPrevious => splits.into_iter().fold(
+                vec![],
+                |mut acc: Vec<std::ops::Range<usize>>, (range, is_match)| {
+                    match acc.last_mut() {
+                        Some(last) if is_match && !previous_match => last.end = range.end,
+                        _ => acc.push(range),
+                    }
+                    previous_match = is_match;
+                    acc
+                },
+            ),
+            SplitDelimiterBehavior::MergedWithNext => {
+                let mut merged = splits.into_iter().rev().fold(
+                    vec![],
+                    |mut acc: Vec<std::ops::Range<usize>>, (range, is_match)| {
+                        match acc.last_mut() {
+                            Some(last) if is_match && !previous_match => last.start = range.start,
+                            _ => acc.push(range),
+                        }
+                        previous_match = is_match;
+                        acc
+                    },
+                );
+                merged.reverse();
+                merged
+            }
+        };
+
+        // Pieces are ordered, so we can keep track of the char offsets as we go
+        let (mut byte_pos, mut char_pos) = (0, 0);
+        splits
+            .into_iter()
+            .map(|range| {
+                let start = char_pos + self.normalized[byte_pos..range.start].chars().count();
+                let end = start + self.normalized[range.clone()].chars().count();
+                byte_pos = range.end;
+                char_pos = end;
+
+                let normalized = self
+                    .slice_bytes(Range::Normalized(range))
+                    .expect("NormalizedString bad split");
+                (normalized, (start, end))
+            })
+            .collect()
+    }
+
     /// Merge with the given NormalizedString by appending it to self
     pub fn merge_with(&mut self, other: &NormalizedString) {
+        // The alignments of `other` are relative to its own original string
+        let len = self.len_original();
         self.original.push_str(&other.original);
-        let len = self.len() - 1;
         self.alignments.extend(
             other
                 .alignments
@@ -514,6 +678,93 @@ impl NormalizedString {
     }
 }
 
+/// What to do with a delimiter once it has been matched
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SplitDelimiterBehavior {
+    /// The delimiter is dropped, only the pieces around it are kept
+    Removed,
+    /// The delimiter is kept as a piece of its own
+    Isolated,
+    /// The delimiter is appended to the piece before it
+    MergedWithPrevious,
+    /// The delimiter is prepended to the piece after it
+    MergedWithNext,
+}
+
+impl Default for SplitDelimiterBehavior {
+    fn default() -> Self {
+        SplitDelimiterBehavior::Removed
+    }
+}
+
+/// Something that can be searched for in a string, to split a `NormalizedString`
+pub trait Pattern {
+    /// Returns the byte ranges of all the non-overlapping matches in `inside`, in order
+    fn find_matches(&self, inside: &str) -> Vec<std::ops::Range<usize>>;
+}
+
+impl Pattern for char {
+    fn find_matches(&self, inside: &str) -> Vec<std::ops::Range<usize>> {
+        inside
+            .match_indices(*self)
+            .map(|(start, m)| start..start + m.len())
+            .collect()
+    }
+}
+
+impl Pattern for &str {
+    fn find_matches(&self, inside: &str) -> Vec<std::ops::Range<usize>> {
+        if self.is_empty() {
+            return vec![];
+        }
+        inside
+            .match_indices(*self)
+            .map(|(start, m)| start..start + m.len())
+            .collect()
+    }
+}
+
+/// Matches any of the given strings. When several of them match at the same position, the
+/// longest one wins.
+impl Pattern for &[String] {
+    fn find_matches(&self, inside: &str) -> Vec<std::ops::Range<usize>> {
+        let mut matches = vec![];
+        let mut i = 0;
+        while i < inside.len() {
+            let rest = &inside[i..];
+            match self
+                .iter()
+                .filter(|s| !s.is_empty() && rest.starts_with(s.as_str()))
+                .map(|s| s.len())
+                .max()
+            {
+                Some(len) => {
+                    matches.push(i..i + len);
+                    i += len;
+                }
+                None => i += rest.chars().next().map_or(1, |c| c.len_utf8()),
+            }
+        }
+        matches
+    }
+}
+
+impl Pattern for &regex::Regex {
+    fn find_matches(&self, inside: &str) -> Vec<std::ops::Range<usize>> {
+        self.find_iter(inside).map(|m| m.start()..m.end()).collect()
+    }
+}
+
+impl<F: Fn(char) -> bool> Pattern for F {
+    fn find_matches(&self, inside: &str) -> Vec<std::ops::Range<usize>> {
+        inside
+            .char_indices()
+            .filter(|(_, c)| self(*c))
+            .map(|(start, c)| start..start + c.len_utf8())
+            .collect()
+    }
+}
+
 /// Returns a range of the given string slice, by indexing chars instead of bytes
 pub fn get_range_of<T: RangeBounds<usize>>(s: &str, range: T) -> Option<&str> {
     let len = s.chars().count();
@@ -545,6 +796,54 @@ pub fn get_range_of<T: RangeBounds<usize>>(s: &str, range: T) -> Option<&str> {
     }
 }
 
+/// Defines how the invalid UTF-8 sequences are handled when converting bytes to a string.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InvalidUtf8 {
+    /// Each invalid sequence gets replaced by the given char. Using `U+FFFD` matches the
+    /// behavior of `String::from_utf8_lossy`.
+    Replace(char),
+    /// Invalid sequences are simply removed.
+    Drop,
+}
+
+impl Default for InvalidUtf8 {
+    fn default() -> Self {
+        InvalidUtf8::Replace(std::char::REPLACEMENT_CHARACTER)
+    }
+}
+
+/// Converts the given bytes to a string, handling any invalid UTF-8 sequence as specified.
+/// This works just like `String::from_utf8_lossy`, but lets us choose the replacement, so that
+/// these sequences are not mixed up with some actual `U+FFFD` in the input.
+pub fn from_utf8_lossy_with(bytes: &[u8], invalid: InvalidUtf8) -> Cow<str> {
+    let mut output = String::with_capacity(bytes.len());
+    let mut remaining = bytes;
+    loop {
+        match std::str::from_utf8(remaining) {
+            Ok(valid) => {
+                if remaining.len() == bytes.len() {
+                    return Cow::Borrowed(valid);
+                }
+                output.push_str(valid);
+                return Cow::Owned(output);
+            }
+            Err(error) => {
+                let (valid, after_valid) = remaining.split_at(error.valid_up_to());
+                // This is safe because `valid_up_to` gives us the length of the valid prefix
+                output.push_str(unsafe { std::str::from_utf8_unchecked(valid) });
+                if let InvalidUtf8::Replace(c) = invalid {
+                    output.push(c);
+                }
+                match error.error_len() {
+                    Some(len) => remaining = &after_valid[len..],
+                    // An incomplete sequence at the very end of the input
+                    None => return Cow::Owned(output),
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -590,6 +889,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn replace() {
+        let mut n = NormalizedString::from("a\t\tb\t\t");
+        n.replace("\t\t", " ");
+        assert_eq!(n.get(), "a b ");
+        assert_eq!(&n.alignments, &[(0, 1), (1, 2), (3, 4), (4, 5)]);
+
+        let mut n = NormalizedString::from("\tab\t");
+        n.replace("\t", "");
+        assert_eq!(n.get(), "ab");
+        assert_eq!(&n.alignments, &[(1, 2), (2, 3)]);
+
+        let mut n = NormalizedString::from("a\tb");
+        n.replace("\t", "  ");
+        assert_eq!(n.get(), "a  b");
+        assert_eq!(&n.alignments, &[(0, 1), (1, 2), (1, 2), (2, 3)]);
+    }
+
+    #[test]
+    fn replace_regex() {
+        let mut n = NormalizedString::from("a \t b!!!");
+        n.replace_regex(&regex::Regex::new(r"\s+|!*").unwrap(), "_");
+        assert_eq!(n.get(), "a_b_");
+        assert_eq!(&n.alignments, &[(0, 1), (1, 2), (4, 5), (5, 6)]);
+    }
+
     #[test]
     fn mixed_addition_and_removal() {
         let mut n = NormalizedString::from("élégant");
@@ -755,6 +1080,29 @@ mod tests {
         assert_eq!(n.convert_offsets(Range::Normalized(0..4)), Some(0..0));
     }
 
+    #[test]
+    fn prepend_keeps_original_offsets() {
+        let mut n = NormalizedString::from("Héllo");
+        n.prepend(" ");
+        assert_eq!(n.get(), " Héllo");
+        assert_eq!(n.len(), 6);
+        assert_eq!(n.len_original(), 5);
+
+        // The inserted space doesn't exist in the original string
+        assert_eq!(n.convert_offsets(Range::Normalized(0..1)), Some(0..0));
+        // The real characters are shifted by one
+        assert_eq!(n.convert_offsets(Range::Normalized(1..2)), Some(0..1));
+        assert_eq!(n.convert_offsets(Range::Normalized(2..6)), Some(1..5));
+        assert_eq!(n.convert_offsets(Range::Normalized(..)), Some(0..5));
+        assert_eq!(n.convert_offsets(Range::Original(1..3)), Some(2..4));
+        assert_eq!(n.get_range_original(Range::Normalized(2..3)), Some("é"));
+
+        let mut n = NormalizedString::from("");
+        n.prepend(" ");
+        assert_eq!(n.len_original(), 0);
+        assert_eq!(n.convert_offsets(Range::Normalized(0..1)), Some(0..0));
+    }
+
     #[test]
     fn append() {
         let mut n = NormalizedString::from("Hey");
@@ -886,4 +1234,94 @@ mod tests {
         );
         assert_eq!(s.slice_bytes(Range::Original(0..10)), None);
     }
+
+    #[test]
+    fn utf8_lossy() {
+        let bytes = b"Hello \xF0\x90\x80World\xFF!";
+        assert_eq!(
+            from_utf8_lossy_with(bytes, InvalidUtf8::default()),
+            String::from_utf8_lossy(bytes)
+        );
+        assert_eq!(
+            from_utf8_lossy_with(bytes, InvalidUtf8::Replace('?')),
+            "Hello ?World?!"
+        );
+        assert_eq!(
+            from_utf8_lossy_with(bytes, InvalidUtf8::Drop),
+            "Hello World!"
+        );
+        // Incomplete sequence at the end
+        assert_eq!(
+            from_utf8_lossy_with(b"Hey\xE2\x82", InvalidUtf8::Drop),
+            "Hey"
+        );
+        // Valid input is just borrowed
+        assert!(matches!(
+            from_utf8_lossy_with(b"Hey", InvalidUtf8::Drop),
+            Cow::Borrowed("Hey")
+        ));
+    }
+
+    #[test]
+    fn split() {
+        let n = NormalizedString::from("The-final--countdown");
+        let test = |behavior: SplitDelimiterBehavior, expected: &[(&str, Offsets)]| {
+            let pieces = n.split('-', behavior);
+            assert_eq!(
+                pieces
+                    .iter()
+                    .map(|(piece, offsets)| (piece.get(), *offsets))
+                    .collect::<Vec<_>>(),
+                expected
+            );
+        };
+
+        test(
+            SplitDelimiterBehavior::Removed,
+            &[("The", (0, 3)), ("final", (4, 9)), ("countdown", (11, 20))],
+        );
+        test(
+            SplitDelimiterBehavior::Isolated,
+            &[
+                ("The", (0, 3)),
+                ("-", (3, 4)),
+                ("final", (4, 9)),
+                ("-", (9, 10)),
+                ("-", (10, 11)),
+                ("countdown", (11, 20)),
+            ],
+        );
+        test(
+            SplitDelimiterBehavior::MergedWithPrevious,
+            &[
+                ("The-", (0, 4)),
+                ("final-", (4, 10)),
+                ("-", (10, 11)),
+                ("countdown", (11, 20)),
+            ],
+        );
+        test(
+            SplitDelimiterBehavior::MergedWithNext,
+            &[
+                ("The", (0, 3)),
+                ("-final", (3, 9)),
+                ("-", (9, 10)),
+                ("-countdown", (10, 20)),
+            ],
+        );
+    }
+
+    #[test]
+    fn split_keeps_alignments() {
+        let mut n = NormalizedString::from("Héllo wörld");
+        n.nfd();
+        let pieces = n.split(|c: char| c.is_whitespace(), SplitDelimiterBehavior::Removed);
+
+        assert_eq!(pieces.len(), 2);
+        let (world, offsets) = &pieces[1];
+        assert_eq!(*offsets, (7, 13));
+        assert_eq!(world.get_original(), "wörld");
+        assert_eq!(world.len(), 6);
+        assert_eq!(world.convert_offsets(Range::Normalized(1..3)), Some(1..2));
+    }
 }
@@ -74,19 +74,26 @@ impl<'de> Visitor<'de> for TokenizerVisitor {
     {
         let mut tokenizer = Tokenizer::new(Box::new(BPE::default()));
         let mut tokens: Vec<AddedTokenWithId> = vec![];
+        let mut padding = None;
+        let mut version = None;
         while let Some(key) = map.next_key::<String>()? {
             match key.as_ref() {
                 "version" => {
-                    let v: String = map.next_value()?;
-                    if &v != "1.0" {
-                        return Err(Error::custom(format!("Unknown tokenizer version '{}'", v)));
+                    version = map.next_value::<Option<String>>()?;
+                    if let Some(v) = &version {
+                        if v != SERIALIZATION_VERSION {
+                            return Err(Error::custom(format!(
+                                "Unknown tokenizer version '{}'",
+                                v
+                            )));
+                        }
                     }
                 }
                 "truncation" => {
                     tokenizer.with_truncation(map.next_value()?);
                 }
                 "padding" => {
-                    tokenizer.with_padding(map.next_value()?);
+                    padding = map.next_value()?;
                 }
                 "added_tokens" => {
                     tokens = map.next_value()?;
@@ -118,9 +125,18 @@ impl<'de> Visitor<'de> for TokenizerVisitor {
             };
         }
 
+        // Some exporters don't specify any version, we consider it to be the current one
+        if version.is_none() {
+            println!(
+                "Warning: Missing tokenizer version, assuming version '{}'",
+                SERIALIZATION_VERSION
+            );
+        }
+
         // We take care of deserializing the added_tokens (instead of `AddedVocabulary` directly
         // because it let us check that associated IDs are still good, and warn the user otherwise
-        for token in tokens {
+        for mut token in tokens {
+            token.token.normalized = token.normalized.unwrap_or(!token.special);
             let tk = token.token.content.clone();
             if token.special {
                 tokenizer.add_special_tokens(&[token.token]);
@@ -143,6 +159,9 @@ impl<'de> Visitor<'de> for TokenizerVisitor {
             }
         }
 
+        // The padding is checked against the vocabulary, so we set it once everything is there
+        tokenizer.with_padding(padding);
+
         Ok(tokenizer)
     }
 }
@@ -2,3 +2,4 @@ pub mod iter;
 pub mod padding;
 pub mod parallelism;
 pub mod truncation;
+pub mod vocab;
@@ -22,10 +22,15 @@ impl std::convert::AsRef<str> for PaddingDirection {
 pub struct PaddingParams {
     pub strategy: PaddingStrategy,
     pub direction: PaddingDirection,
+    #[serde(default)]
     pub pad_to_multiple_of: Option<usize>,
     pub pad_id: u32,
     pub pad_type_id: u32,
     pub pad_token: String,
+    /// Whether encoding should fail when `pad_token` doesn't match `pad_id` in the vocabulary.
+    /// We only warn about it otherwise.
+    #[serde(default)]
+    pub strict: bool,
 }
 
 impl Default for PaddingParams {
@@ -37,10 +42,46 @@ impl Default for PaddingParams {
             pad_id: 0,
             pad_type_id: 0,
             pad_token: String::from("[PAD]"),
+            strict: false,
         }
     }
 }
 
+#[derive(Debug)]
+pub enum PaddingError {
+    /// The padding token doesn't have the padding id in the vocabulary.
+    MismatchedPadToken {
+        pad_token: String,
+        pad_id: u32,
+        vocab_id: Option<u32>,
+    },
+}
+
+impl std::fmt::Display for PaddingError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PaddingError::MismatchedPadToken {
+                pad_token,
+                pad_id,
+                vocab_id,
+            } => match vocab_id {
+                Some(id) => write!(
+                    fmt,
+                    "Padding error: pad_token '{}' has ID '{}' in the vocabulary \
+                        but pad_id is '{}'",
+                    pad_token, id, pad_id
+                ),
+                None => write!(
+                    fmt,
+                    "Padding error: pad_token '{}' is not part of the vocabulary",
+                    pad_token
+                ),
+            },
+        }
+    }
+}
+impl std::error::Error for PaddingError {}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PaddingStrategy {
     BatchLongest,
@@ -121,6 +162,7 @@ mod tests {
             pad_id: 0,
             pad_type_id: 0,
             pad_token: String::from("[PAD]"),
+            strict: false,
         };
         pad_encodings(&mut encodings, &params).unwrap();
         assert!(encodings.iter().all(|e| e.get_ids().len() == 8));
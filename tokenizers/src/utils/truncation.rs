@@ -6,6 +6,8 @@ pub struct TruncationParams {
     pub max_length: usize,
     pub strategy: TruncationStrategy,
     pub stride: usize,
+    #[serde(default)]
+    pub direction: TruncationDirection,
 }
 
 impl Default for TruncationParams {
@@ -14,6 +16,31 @@ impl Default for TruncationParams {
             max_length: 512,
             strategy: TruncationStrategy::LongestFirst,
             stride: 0,
+            direction: TruncationDirection::Right,
+        }
+    }
+}
+
+/// The side from which we remove tokens when truncating.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TruncationDirection {
+    /// Remove tokens from the beginning, keeping the end of the sequence
+    Left,
+    /// Remove tokens from the end, keeping the beginning of the sequence
+    Right,
+}
+
+impl Default for TruncationDirection {
+    fn default() -> Self {
+        TruncationDirection::Right
+    }
+}
+
+impl std::convert::AsRef<str> for TruncationDirection {
+    fn as_ref(&self) -> &str {
+        match self {
+            TruncationDirection::Left => "left",
+            TruncationDirection::Right => "right",
         }
     }
 }
@@ -66,10 +93,29 @@ impl std::convert::AsRef<str> for TruncationStrategy {
 }
 
 pub fn truncate_encodings(
+    encoding: Encoding,
+    pair_encoding: Option<Encoding>,
+    params: &TruncationParams,
+) -> Result<(Encoding, Option<Encoding>)> {
+    truncate_encodings_at_words(encoding, pair_encoding, params, (false, false))
+}
+
+/// Same as `truncate_encodings`, but `at_words` tells, for each of the sequences, whether the
+/// overflowing windows should avoid cutting words. This is the case for pre-tokenized inputs.
+pub(crate) fn truncate_encodings_at_words(
     mut encoding: Encoding,
     mut pair_encoding: Option<Encoding>,
     params: &TruncationParams,
+    at_words: (bool, bool),
 ) -> Result<(Encoding, Option<Encoding>)> {
+    let truncate = |encoding: &mut Encoding, max_len: usize, at_words: bool| {
+        if at_words {
+            encoding.truncate_at_words(max_len, params.stride, params.direction);
+        } else {
+            encoding.truncate_with_direction(max_len, params.stride, params.direction);
+        }
+    };
+
     if params.max_length == 0 {
         return Err(Box::new(TruncationError::MaxLengthTooLow));
     }
@@ -101,23 +147,23 @@ pub fn truncate_encodings(
                 return Err(Box::new(TruncationError::MaxLengthTooLow));
             }
 
-            encoding.truncate(n_first, params.stride);
+            truncate(&mut encoding, n_first, at_words.0);
             if let Some(encoding) = pair_encoding.as_mut() {
-                encoding.truncate(n_second, params.stride);
+                truncate(encoding, n_second, at_words.1);
             }
         }
         TruncationStrategy::OnlyFirst | TruncationStrategy::OnlySecond => {
-            let target = if params.strategy == TruncationStrategy::OnlyFirst {
-                Ok(&mut encoding)
+            let (target, at_words) = if params.strategy == TruncationStrategy::OnlyFirst {
+                Ok((&mut encoding, at_words.0))
             } else if let Some(encoding) = pair_encoding.as_mut() {
-                Ok(encoding)
+                Ok((encoding, at_words.1))
             } else {
                 Err(Box::new(TruncationError::SecondSequenceNotProvided))
             }?;
 
             let target_len = target.get_ids().len();
             if target_len > to_remove {
-                target.truncate(target_len - to_remove, params.stride);
+                truncate(target, target_len - to_remove, at_words);
             } else {
                 return Err(Box::new(TruncationError::SequenceTooShort));
             }
@@ -126,3 +172,57 @@ pub fn truncate_encodings(
 
     Ok((encoding, pair_encoding))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Token;
+
+    fn get_long_encoding() -> Encoding {
+        Encoding::from_tokens(
+            (0..6)
+                .map(|i| Token::new(i, i.to_string(), (i as usize, i as usize + 1), i))
+                .collect(),
+            0,
+        )
+    }
+
+    #[test]
+    fn truncate_right() {
+        let params = TruncationParams {
+            max_length: 4,
+            direction: TruncationDirection::Right,
+            ..Default::default()
+        };
+        let (encoding, _) = truncate_encodings(get_long_encoding(), None, &params).unwrap();
+        assert_eq!(encoding.get_ids(), &[0, 1, 2, 3]);
+        assert_eq!(encoding.get_tokens(), &["0", "1", "2", "3"]);
+        assert_eq!(encoding.get_offsets(), &[(0, 1), (1, 2), (2, 3), (3, 4)]);
+        assert_eq!(encoding.get_overflowing()[0].get_ids(), &[4, 5]);
+    }
+
+    #[test]
+    fn truncate_left() {
+        let params = TruncationParams {
+            max_length: 4,
+            direction: TruncationDirection::Left,
+            ..Default::default()
+        };
+        let (encoding, _) = truncate_encodings(get_long_encoding(), None, &params).unwrap();
+        assert_eq!(encoding.get_ids(), &[2, 3, 4, 5]);
+        assert_eq!(encoding.get_tokens(), &["2", "3", "4", "5"]);
+        assert_eq!(encoding.get_words(), &[Some(2), Some(3), Some(4), Some(5)]);
+        assert_eq!(encoding.get_offsets(), &[(2, 3), (3, 4), (4, 5), (5, 6)]);
+        assert_eq!(encoding.get_attention_mask(), &[1, 1, 1, 1]);
+        assert_eq!(encoding.get_overflowing()[0].get_ids(), &[0, 1]);
+
+        // With a stride, the overflowing part overlaps with the kept one
+        let params = TruncationParams {
+            stride: 1,
+            ..params
+        };
+        let (encoding, _) = truncate_encodings(get_long_encoding(), None, &params).unwrap();
+        assert_eq!(encoding.get_ids(), &[2, 3, 4, 5]);
+        assert_eq!(encoding.get_overflowing()[0].get_ids(), &[0, 1, 2]);
+    }
+}
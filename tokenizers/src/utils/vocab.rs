@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+/// The differences between two vocabularies, as returned by `diff_vocabs`. Each list is sorted
+/// by id, then by token.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct VocabDiff {
+    /// The tokens only in the second vocabulary, with their id there
+    pub added: Vec<(String, u32)>,
+    /// The tokens only in the first vocabulary, with their id there
+    pub removed: Vec<(String, u32)>,
+    /// The tokens in both vocabularies but with different ids, as `(token, old_id, new_id)`
+    pub reassigned: Vec<(String, u32, u32)>,
+}
+
+impl VocabDiff {
+    /// Whether both vocabularies are the same
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.reassigned.is_empty()
+    }
+}
+
+/// Compare the vocabulary `a` to the vocabulary `b`, like the ones given by
+/// `Tokenizer::get_vocab` for two tokenizers.
+pub fn diff_vocabs(a: &HashMap<String, u32>, b: &HashMap<String, u32>) -> VocabDiff {
+    let mut diff = VocabDiff::default();
+    for (token, id) in a {
+        match b.get(token) {
+            None => diff.removed.push((token.clone(), *id)),
+            Some(new_id) if new_id != id => diff.reassigned.push((token.clone(), *id, *new_id)),
+            Some(_) => {}
+        }
+    }
+    diff.added = b
+        .iter()
+        .filter(|(token, _)| !a.contains_key(*token))
+        .map(|(token, id)| (token.clone(), *id))
+        .collect();
+
+    let VocabDiff {
+        added,
+        removed,
+        reassigned,
+    } = &mut diff;
+    added.sort_by(|x, y| (x.1, &x.0).cmp(&(y.1, &y.0)));
+    removed.sort_by(|x, y| (x.1, &x.0).cmp(&(y.1, &y.0)));
+    reassigned.sort_by(|x, y| (x.1, &x.0).cmp(&(y.1, &y.0)));
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff() {
+        let vocab = |tokens: &[(&str, u32)]| {
+            tokens
+                .iter()
+                .map(|(t, id)| (t.to_string(), *id))
+                .collect::<HashMap<_, _>>()
+        };
+        let a = vocab(&[("a", 0), ("b", 1), ("c", 2), ("ab", 3)]);
+        let b = vocab(&[("a", 0), ("c", 1), ("b", 2), ("bc", 3), ("abc", 4)]);
+
+        assert_eq!(
+            diff_vocabs(&a, &b),
+            VocabDiff {
+                added: vec![("bc".into(), 3), ("abc".into(), 4)],
+                removed: vec![("ab".into(), 3)],
+                reassigned: vec![("b".into(), 1, 2), ("c".into(), 2, 1)],
+            }
+        );
+        assert!(diff_vocabs(&a, &a).is_empty());
+    }
+}
@@ -28,6 +28,30 @@ fn add_tokens() {
     assert_eq!(tokenizer.token_to_id("world"), Some(3));
 }
 
+#[test]
+fn add_special_tokens_existing_in_model() {
+    let mut tokenizer = get_bert();
+    let vocab_size = tokenizer.get_vocab_size(true);
+    let sep_id = tokenizer.token_to_id("[SEP]");
+    assert!(sep_id.is_some());
+
+    assert_eq!(
+        tokenizer.add_special_tokens(&[AddedToken::from("[SEP]", true)]),
+        0
+    );
+    assert_eq!(tokenizer.get_vocab_size(true), vocab_size);
+    assert_eq!(tokenizer.token_to_id("[SEP]"), sep_id);
+
+    // It is now handled as a special token
+    let output = tokenizer.encode("hello [SEP] world", false).unwrap();
+    assert_eq!(output.get_tokens(), &["hello", "[SEP]", "world"]);
+    assert_eq!(output.get_ids()[1], sep_id.unwrap());
+    assert_eq!(
+        tokenizer.decode(output.get_ids().to_vec(), true).unwrap(),
+        "hello world"
+    );
+}
+
 #[test]
 fn lstrip_tokens() {
     let mut tokenizer = get_byte_level(true, false);
@@ -84,6 +108,16 @@ fn single_word_tokens() {
 
     assert_eq!(output.get_tokens(), &["I", "Ġlike", "Ġdancing"]);
 
+    // But it still matches the standalone word, keeping the offsets around it right
+    let input = "I like dancing ing";
+    let output = tokenizer.encode(input, false).unwrap();
+
+    assert_eq!(output.get_tokens(), &["I", "Ġlike", "Ġdancing", "Ġ", "ing"]);
+    assert_eq!(
+        output.get_offsets(),
+        &[(0, 1), (1, 6), (6, 14), (14, 15), (15, 18)]
+    );
+
     // If `single_word = false` it should split `dancing`
     let mut tokenizer = get_byte_level(false, false);
     tokenizer.add_special_tokens(&[AddedToken::from("ing", true).single_word(false)]);
@@ -118,3 +152,29 @@ fn overlapping_tokens() {
 
     assert_eq!(output.get_tokens(), &["I", "Ġl", "ike", "Ġda", "nci", "ng"]);
 }
+
+#[test]
+fn longest_token_at_same_position() {
+    let mut tokenizer = get_byte_level(false, false);
+
+    tokenizer.add_tokens(&[AddedToken::from("New", false)]);
+    tokenizer.add_tokens(&[AddedToken::from("New York", false)]);
+
+    let output = tokenizer.encode("New York City", false).unwrap();
+
+    assert_eq!(output.get_tokens(), &["New York", "ĠCity"]);
+    assert_eq!(output.get_offsets(), &[(0, 8), (8, 13)]);
+}
+
+#[test]
+fn case_insensitive_tokens() {
+    let mut tokenizer = get_byte_level(false, false);
+    tokenizer.add_tokens(&[AddedToken::from("USA", false).lowercase(true)]);
+
+    let output = tokenizer.encode("usa Today", false).unwrap();
+
+    // The added token matches, but the text keeps its original case everywhere
+    assert_eq!(output.get_tokens(), &["usa", "ĠToday"]);
+    assert_eq!(output.get_ids()[0], tokenizer.token_to_id("USA").unwrap());
+    assert_eq!(output.get_offsets(), &[(0, 3), (3, 9)]);
+}
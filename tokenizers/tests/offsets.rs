@@ -1,7 +1,12 @@
 mod common;
 
 use common::*;
-use tokenizers::tokenizer::{get_range_of, AddedToken};
+use std::collections::HashMap;
+use tokenizers::models::bpe::BPE;
+use tokenizers::normalizers::replace::Replace;
+use tokenizers::normalizers::utils::Lowercase;
+use tokenizers::pre_tokenizers::whitespace::Whitespace;
+use tokenizers::tokenizer::{get_range_of, AddedToken, Tokenizer};
 
 macro_rules! check_offsets {
     ($input: expr, $output:expr, $offset:expr, $result:expr) => {
@@ -41,6 +46,41 @@ fn byte_level_basic() {
     check_offsets!(input, output, 6, "?");
 }
 
+#[test]
+fn byte_level_offsets_reference_original() {
+    let tokenizer = get_byte_level(false, false);
+
+    let input = "Hello world";
+    let output = tokenizer.encode(input, false).unwrap();
+
+    assert_eq!(output.get_tokens(), &["Hello", "Ġworld"]);
+    check_offsets!(input, output, 0, "Hello");
+    check_offsets!(input, output, 1, " world");
+}
+
+#[test]
+fn byte_level_with_normalizer() {
+    let mut tokenizer = get_byte_level(false, false);
+    tokenizer.with_normalizer(Box::new(Lowercase));
+
+    let input = "HÉLLO";
+    let output = tokenizer.encode(input, false).unwrap();
+
+    let offsets = output.get_offsets();
+    assert_eq!(offsets.first().unwrap().0, 0);
+    assert_eq!(offsets.last().unwrap().1, input.chars().count());
+    for (token, (start, end)) in output.get_tokens().iter().zip(offsets) {
+        let original = get_range_of(input, *start..*end).unwrap();
+        // Any token containing some bytes of `é` must point to the original `É`
+        if token.contains('Ã') || token.contains('©') {
+            assert!(original.contains('É'));
+        }
+        // And the others must still point to their (uppercase) original characters
+        let expected = token.replace('Ã', "").replace('©', "").to_uppercase();
+        assert!(original.contains(&expected));
+    }
+}
+
 #[test]
 fn byte_level_unicode() {
     let tokenizer = get_byte_level(true, false);
@@ -178,3 +218,22 @@ fn split_on_added_tokens_bert() {
         &["yesterday", "i", "saw", "a", "[MASK]", "far", "away"]
     );
 }
+
+#[test]
+fn replace_normalizer() {
+    let vocab: HashMap<String, u32> = [("a".into(), 0), ("b".into(), 1)].iter().cloned().collect();
+    let bpe = BPE::builder()
+        .vocab_and_merges(vocab, HashMap::new())
+        .build()
+        .unwrap();
+    let mut tokenizer = Tokenizer::new(Box::new(bpe));
+    tokenizer.with_normalizer(Box::new(Replace::new("\t", " ").unwrap()));
+    tokenizer.with_pre_tokenizer(Box::new(Whitespace));
+
+    let input = "a\tb";
+    let output = tokenizer.encode(input, false).unwrap();
+
+    assert_eq!(output.get_tokens(), &["a", "b"]);
+    assert_eq!(output.get_offsets(), &[(0, 1), (2, 3)]);
+    check_offsets!(input, output, 1, "b");
+}
@@ -1,7 +1,16 @@
 mod common;
 
 use common::*;
+use tokenizers::decoders::metaspace::Metaspace;
 use tokenizers::models::wordlevel::WordLevel;
+use tokenizers::normalizers::strip::Strip;
+use tokenizers::normalizers::utils::{Lowercase, Sequence};
+use tokenizers::pre_tokenizers::delimiter::CharDelimiterSplit;
+use tokenizers::processors::roberta::RobertaProcessing;
+use tokenizers::tokenizer::{
+    AddedToken, NormalizedString, Normalizer, PaddingParams, PaddingStrategy, Tokenizer,
+    TruncationParams,
+};
 
 #[test]
 fn bpe_serde() {
@@ -26,3 +35,135 @@ fn wordlevel_serde() {
     let de = serde_json::from_str(&ser).unwrap();
     assert_eq!(wordlevel, de);
 }
+
+#[test]
+fn nested_normalizers_sequence_serde() {
+    // The format used by the bindings
+    let json = r#"{"type":"Sequence","normalizers":[{"type":"Strip","strip_left":true,"strip_right":true},{"type":"Sequence","normalizers":[{"type":"Lowercase"}]}]}"#;
+    let normalizer: Box<dyn Normalizer> = serde_json::from_str(json).unwrap();
+    assert_eq!(serde_json::to_string(&normalizer).unwrap(), json);
+
+    let mut normalized = NormalizedString::from("  Hello ");
+    normalizer.normalize(&mut normalized).unwrap();
+    assert_eq!(normalized.get(), "hello");
+
+    let normalizer: Box<dyn Normalizer> = Box::new(Sequence::new(vec![
+        Box::new(Strip::new(true, true)),
+        Box::new(Sequence::new(vec![Box::new(Lowercase)])),
+    ]));
+    assert_eq!(serde_json::to_string(&normalizer).unwrap(), json);
+}
+
+#[test]
+fn tokenizer_serde_missing_optional_fields() {
+    // A tokenizer saved before some optional fields got introduced
+    let json = r#"{
+        "version": "1.0",
+        "truncation": null,
+        "padding": {
+            "strategy": "BatchLongest",
+            "direction": "Right",
+            "pad_id": 0,
+            "pad_type_id": 0,
+            "pad_token": "[PAD]"
+        },
+        "added_tokens": [
+            { "id": 2, "special": true, "content": "[PAD]" }
+        ],
+        "normalizer": null,
+        "pre_tokenizer": null,
+        "post_processor": null,
+        "decoder": null,
+        "model": {
+            "type": "BPE",
+            "dropout": null,
+            "unk_token": null,
+            "continuing_subword_prefix": null,
+            "end_of_word_suffix": null,
+            "vocab": { "a": 0, "b": 1 },
+            "merges": []
+        }
+    }"#;
+    let tokenizer: Tokenizer = json.parse().unwrap();
+
+    let padding = tokenizer.get_padding().unwrap();
+    assert!(matches!(padding.strategy, PaddingStrategy::BatchLongest));
+    assert_eq!(padding.pad_to_multiple_of, None);
+    assert_eq!(tokenizer.token_to_id("[PAD]"), Some(2));
+    assert_eq!(tokenizer.get_vocab_size(true), 3);
+    // Special tokens are not normalized by default
+    let ser = serde_json::to_value(&tokenizer).unwrap();
+    assert_eq!(ser["added_tokens"][0]["normalized"], false);
+}
+
+#[test]
+fn tokenizer_serde_missing_version() {
+    let tokenizer = get_bert();
+    let ser = serde_json::to_value(&tokenizer).unwrap();
+
+    let mut without_version = ser.clone();
+    without_version.as_object_mut().unwrap().remove("version");
+    let de: Tokenizer = serde_json::from_value(without_version).unwrap();
+    assert_eq!(serde_json::to_value(&de).unwrap(), ser);
+
+    let mut null_version = ser.clone();
+    null_version["version"] = serde_json::Value::Null;
+    let de: Tokenizer = serde_json::from_value(null_version).unwrap();
+    assert_eq!(serde_json::to_value(&de).unwrap(), ser);
+
+    let mut unknown_version = ser;
+    unknown_version["version"] = "42.0".into();
+    assert!(serde_json::from_value::<Tokenizer>(unknown_version).is_err());
+}
+
+/// Serialize the given tokenizer, deserialize it, and check that both encode and decode the
+/// given input in the exact same way.
+fn check_round_trip(tokenizer: &Tokenizer, input: &str) {
+    let ser = serde_json::to_value(tokenizer).unwrap();
+    let de: Tokenizer = serde_json::from_value(ser.clone()).unwrap();
+    assert_eq!(serde_json::to_value(&de).unwrap(), ser);
+
+    let encoding = tokenizer.encode(input, true).unwrap();
+    assert_eq!(de.encode(input, true).unwrap(), encoding);
+    assert_eq!(
+        de.decode(encoding.get_ids().to_vec(), true).unwrap(),
+        tokenizer.decode(encoding.get_ids().to_vec(), true).unwrap()
+    );
+}
+
+#[test]
+fn tokenizer_serde_full_stack() {
+    let input = "  Hello there [MASK], how are you?  ";
+
+    // Normalizer, PreTokenizer, Model, PostProcessor and Decoder, with truncation, padding and
+    // added tokens
+    let mut tokenizer = get_bert();
+    tokenizer.add_special_tokens(&[AddedToken::from("[MASK]", true)]);
+    tokenizer.add_tokens(&[AddedToken::from("how are", false)]);
+    tokenizer.with_truncation(Some(TruncationParams {
+        max_length: 8,
+        stride: 2,
+        ..Default::default()
+    }));
+    tokenizer.with_padding(Some(PaddingParams {
+        strategy: PaddingStrategy::Fixed(10),
+        ..Default::default()
+    }));
+    check_round_trip(&tokenizer, input);
+
+    let mut tokenizer = get_byte_level(true, false);
+    tokenizer.with_normalizer(Box::new(Sequence::new(vec![
+        Box::new(Strip::new(true, true)),
+        Box::new(Lowercase),
+    ])));
+    tokenizer.with_pre_tokenizer(Box::new(Metaspace::new('Ġ', true)));
+    tokenizer.with_decoder(Box::new(Metaspace::new('Ġ', true)));
+    tokenizer.with_post_processor(Box::new(RobertaProcessing::new(
+        ("</s>".into(), 2),
+        ("<s>".into(), 0),
+    )));
+    check_round_trip(&tokenizer, input);
+
+    tokenizer.with_pre_tokenizer(Box::new(CharDelimiterSplit::new(' ')));
+    check_round_trip(&tokenizer, input);
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::io::Write;
+use tokenizers::decoders::wordpiece::WordPiece as WordPieceDecoder;
+use tokenizers::models::bpe::{BpeTrainer, BPE};
+use tokenizers::models::wordpiece::{WordPiece, WordPieceTrainer};
+use tokenizers::normalizers::bert::BertNormalizer;
+use tokenizers::pre_tokenizers::bert::BertPreTokenizer;
+use tokenizers::tokenizer::{AddedToken, Model, Tokenizer, Trainer};
+
+#[test]
+fn wordpiece_train_encode_decode() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    file.write_all(
+        b"Hello there, how are you?\n\
+          The weather is great today, isn't it?\n\
+          A zebra is running there.\n",
+    )
+    .unwrap();
+
+    let mut tokenizer = Tokenizer::new(Box::new(WordPiece::default()));
+    tokenizer.with_normalizer(Box::new(BertNormalizer::default()));
+    tokenizer.with_pre_tokenizer(Box::new(BertPreTokenizer));
+    tokenizer.with_decoder(Box::new(WordPieceDecoder::default()));
+
+    let trainer: Box<dyn Trainer> = Box::new(
+        WordPieceTrainer::builder()
+            .vocab_size(60)
+            .show_progress(false)
+            .special_tokens(vec![AddedToken::from("[UNK]", true)])
+            .build(),
+    );
+    tokenizer
+        .train(&trainer, vec![file.path().to_str().unwrap().to_owned()])
+        .unwrap();
+
+    // Each char of the alphabet can also continue a word
+    let vocab = tokenizer.get_vocab(false);
+    for token in vocab.keys().filter(|t| t.chars().count() == 1) {
+        assert!(vocab.contains_key(&format!("##{}", token)), "##{}", token);
+    }
+
+    // The `z` only starts a word in the training data
+    let input = "The weather is hazy there, how are you?";
+    let encoding = tokenizer.encode(input, false).unwrap();
+    assert!(!encoding.get_tokens().iter().any(|t| t == "[UNK]"));
+    assert!(encoding.get_tokens().iter().any(|t| t.starts_with("##")));
+
+    let decoded = tokenizer.decode(encoding.get_ids().to_vec(), true).unwrap();
+    assert_eq!(decoded, input.to_lowercase());
+}
+
+#[test]
+fn bpe_save_load_round_trip() {
+    let word_counts: HashMap<String, u32> = [
+        ("roses", 1),
+        ("are", 2),
+        ("red", 1),
+        ("violets", 1),
+        ("blue", 1),
+        ("is", 2),
+        ("so", 1),
+    ]
+    .iter()
+    .map(|(w, c)| (w.to_string(), *c))
+    .collect();
+    let trainer = BpeTrainer::builder()
+        .show_progress(false)
+        .min_frequency(2)
+        .build();
+    let (bpe, _) = trainer.train(word_counts).unwrap();
+
+    let save = |bpe: &BPE| {
+        let dir = tempfile::tempdir().unwrap();
+        let files = bpe.save(dir.path(), None).unwrap();
+        let contents = files
+            .iter()
+            .map(|f| std::fs::read(f).unwrap())
+            .collect::<Vec<_>>();
+        (dir, files, contents)
+    };
+    let (_dir, files, contents) = save(&bpe);
+    let reloaded = BPE::from_files(files[0].to_str().unwrap(), files[1].to_str().unwrap())
+        .build()
+        .unwrap();
+
+    // Saving again gives the exact same files
+    assert_eq!(save(&reloaded).2, contents);
+
+    let words = vec![
+        ("arose".to_string(), (0, 5)),
+        ("violet".to_string(), (6, 12)),
+        ("is".to_string(), (13, 15)),
+    ];
+    assert_eq!(
+        reloaded.tokenize(words.clone()).unwrap(),
+        bpe.tokenize(words).unwrap()
+    );
+}